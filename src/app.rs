@@ -3,54 +3,249 @@ use super::components::{
         logstab,
         metricstab,
     },
+    selectpopup::SelectPopup,
+    textinput::{TextInputComponent, InputMode},
     Drawable,
+    Navigation,
 };
-use crossterm::event::{KeyEvent, KeyCode};
+use crossterm::event::{KeyEvent, KeyCode, KeyModifiers};
 use anyhow::Result;
+use rusoto_core::Region;
+use std::str::FromStr;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
-use crate::utils::loggroup_menulist::LogGroupMenuList;
+use crate::utils::{loggroup_menulist::LogGroupMenuList, timestamp_format::TimestampFormat};
 use crate::instruction::Instruction;
-use crate::globalstate::{GlobalState, GlobalStateTail};
+use crate::globalstate::{GlobalState, GlobalStateMetrics, GlobalStateTail};
+use crate::aws::{AwsSettings, config::AwsConfig, credentials::MfaPrompt, region::{self, REGIONS}};
 
 pub struct App {
     pub current_tab_idx: usize,
     pub tabs: Vec<Box<dyn Drawable>>,
+    pub region_popup: Option<SelectPopup>,
+    pub profile_popup: Option<SelectPopup>,
+    pub mfa_input: Option<TextInputComponent>,
+    tx: Sender<Instruction>,
+    state: Arc<Mutex<GlobalState>>,
+    tail_state: Arc<Mutex<GlobalStateTail>>,
+    metrics_state: Arc<Mutex<GlobalStateMetrics>>,
+    settings: Arc<Mutex<AwsSettings>>,
+    mfa_prompt: MfaPrompt,
 }
 
 impl App {
-    pub async fn new(tx: Sender<Instruction>, state: Arc<Mutex<GlobalState>>, tail_state: Arc<Mutex<GlobalStateTail>>) -> Result<App> {
+    pub async fn new(
+        tx: Sender<Instruction>,
+        state: Arc<Mutex<GlobalState>>,
+        tail_state: Arc<Mutex<GlobalStateTail>>,
+        metrics_state: Arc<Mutex<GlobalStateMetrics>>,
+        settings: Arc<Mutex<AwsSettings>>,
+        mfa_prompt: MfaPrompt,
+        timestamp_format: TimestampFormat,
+    ) -> Result<App> {
         // TODO: need to fetch log groups
         let log_groups = LogGroupMenuList::new(vec![]);
         let child_tx = Sender::clone(&tx);
         let state0 = Arc::clone(&state);
+        let tail_state0 = Arc::clone(&tail_state);
 
         let tabs: Vec<Box<dyn Drawable>> = vec![
-            Box::new(logstab::LogsTab::new(log_groups, child_tx, state0, tail_state).await?),
-            Box::new(metricstab::MetricsTab::new()),
+            Box::new(logstab::LogsTab::new(log_groups, child_tx, state0, tail_state0, timestamp_format).await?),
+            Box::new(metricstab::MetricsTab::new(
+                Sender::clone(&tx),
+                Arc::clone(&metrics_state),
+                Arc::clone(&settings),
+            )),
         ];
         Ok(App {
             current_tab_idx: 0,
             tabs,
+            region_popup: None,
+            profile_popup: None,
+            mfa_input: None,
+            tx,
+            state,
+            tail_state,
+            metrics_state,
+            settings,
+            mfa_prompt,
         })
     }
 
+    pub fn get_region_name(&self) -> String {
+        self.settings.lock().unwrap().region.name().to_string()
+    }
+
+    pub fn get_endpoint_url(&self) -> Option<String> {
+        self.settings.lock().unwrap().endpoint_url.clone()
+    }
+
+    pub fn get_profile_name(&self) -> String {
+        self.settings.lock().unwrap().get_profile_name()
+    }
+
+    pub fn on_tick(&mut self) {
+        // open the modal when the background tasks are waiting for a MFA token code
+        if self.mfa_input.is_none() {
+            if let Some(serial) = self.mfa_prompt.get_pending_serial() {
+                let mut input = TextInputComponent::new(&format!("MFA token code for {}", serial), "");
+                input.select();
+                input.set_input_mode(InputMode::EditMode);
+                self.mfa_input = Some(input);
+            }
+        }
+    }
+
     pub async fn handle_event(&mut self, event: KeyEvent) {
-        let solved = if let Some(tab) = self.tabs.get_mut(self.current_tab_idx) {
-            tab.handle_event(event).await
+        if let Some(input) = &mut self.mfa_input {
+            match event.code {
+                KeyCode::Enter => {
+                    self.mfa_prompt.answer(Some(input.get_text().to_string()));
+                    self.mfa_input = None;
+                },
+                KeyCode::Esc => {
+                    self.mfa_prompt.answer(None);
+                    self.mfa_input = None;
+                },
+                _ => {
+                    input.handle_event(event).await;
+                },
+            }
+            return;
+        }
+        if let Some(popup) = &mut self.region_popup {
+            if !popup.handle_event(event).await {
+                match event.code {
+                    KeyCode::Enter => {
+                        if let Some(name) = popup.get_selected_item() {
+                            if let Ok(region) = Region::from_str(&name) {
+                                self.switch_region(region);
+                            }
+                        }
+                        self.region_popup = None;
+                    },
+                    KeyCode::Esc => self.region_popup = None,
+                    _ => {}
+                }
+            }
+            return;
+        }
+        if let Some(popup) = &mut self.profile_popup {
+            if !popup.handle_event(event).await {
+                match event.code {
+                    KeyCode::Enter => {
+                        if let Some(profile) = popup.get_selected_item() {
+                            self.switch_profile(profile);
+                        }
+                        self.profile_popup = None;
+                    },
+                    KeyCode::Esc => self.profile_popup = None,
+                    _ => {}
+                }
+            }
+            return;
+        }
+        if event.modifiers == KeyModifiers::CONTROL && event.code == KeyCode::Char('r') {
+            self.open_region_popup();
+            return;
+        }
+        if event.modifiers == KeyModifiers::CONTROL && event.code == KeyCode::Char('p') {
+            self.open_profile_popup();
+            return;
+        }
+        let (solved, navigation) = if let Some(tab) = self.tabs.get_mut(self.current_tab_idx) {
+            (tab.handle_event(event).await, tab.take_navigation())
         } else {
-            false
+            (false, None)
         };
+        if let Some(navigation) = navigation {
+            self.navigate(&navigation);
+            return;
+        }
         if !solved {
             match event.code {
                 KeyCode::Tab => {
-                    self.current_tab_idx = self.get_next_tab_idx();
+                    self.select_tab(self.get_next_tab_idx());
                 },
                 _ => {}
             }
         }
     }
 
+    /// move to the first tab which accepts the navigation
+    fn navigate(&mut self, navigation: &Navigation) {
+        let destination = self.tabs
+            .iter_mut()
+            .position(|tab| tab.navigate(navigation));
+        if let Some(idx) = destination {
+            self.select_tab(idx);
+        }
+    }
+
+    /// only the current tab is drawn, so the tail is stopped here.
+    /// the logs view starts it again when it's drawn in tail mode
+    fn select_tab(&mut self, idx: usize) {
+        if idx != self.current_tab_idx {
+            self.tail_state.lock().unwrap().active = false;
+        }
+        self.current_tab_idx = idx;
+    }
+
+    fn open_region_popup(&mut self) {
+        let names = REGIONS.iter().map(|r| r.name().to_string()).collect();
+        let mut popup = SelectPopup::new("Region", names);
+        popup.select_item(&self.get_region_name());
+        self.region_popup = Some(popup);
+    }
+
+    /// switch region, then reload everything from the new region
+    fn switch_region(&mut self, region: Region) {
+        {
+            let mut settings = self.settings.lock().unwrap();
+            if settings.region == region {
+                return;
+            }
+            settings.region = region;
+        }
+        self.reload();
+    }
+
+    fn open_profile_popup(&mut self) {
+        let mut popup = SelectPopup::new("Profile", AwsConfig::load().get_profile_names());
+        popup.select_item(&self.get_profile_name());
+        self.profile_popup = Some(popup);
+    }
+
+    /// switch profile, then reload everything with the new credentials.
+    /// the region of the profile is used if it's defined in the config file,
+    /// and the endpoint is resolved again for the profile
+    fn switch_profile(&mut self, profile: String) {
+        {
+            let mut settings = self.settings.lock().unwrap();
+            if settings.get_profile_name() == profile {
+                return;
+            }
+            let config = AwsConfig::load();
+            if let Some(region) = config.get(&profile, "region").and_then(|r| r.parse().ok()) {
+                settings.region = region;
+            }
+            settings.endpoint_url = region::resolve_endpoint_url(None, &config, &profile);
+            settings.profile = Some(profile);
+        }
+        self.reload();
+    }
+
+    /// drop the results of the previous account or region and fetch the log groups again
+    fn reload(&mut self) {
+        self.state.lock().unwrap().reset();
+        self.tail_state.lock().unwrap().reset();
+        self.metrics_state.lock().unwrap().reset();
+        for tab in self.tabs.iter_mut() {
+            tab.reset();
+        }
+        self.tx.send(Instruction::FetchLogGroups).unwrap();
+    }
+
     fn get_next_tab_idx(&self) -> usize {
         if self.tabs.len() - 1 == self.current_tab_idx {
             0
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const AWS_CONFIG_FILE: &str = "AWS_CONFIG_FILE";
+const AWS_PROFILE: &str = "AWS_PROFILE";
+const DEFAULT_PROFILE: &str = "default";
+
+/// profiles and their properties parsed from the aws config file (`~/.aws/config`)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AwsConfig {
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl AwsConfig {
+    /// load the config file. returns empty config if it can't be read
+    pub fn load() -> Self {
+        match config_file_path() {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(content) => Self::parse(&content),
+                Err(_) => Self::default(),
+            },
+            None => Self::default(),
+        }
+    }
+
+    /// parse ini formatted config. `[default]` and `[profile name]` sections are supported
+    pub fn parse(content: &str) -> Self {
+        let mut profiles: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        let mut current: Option<String> = None;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let section = line[1..line.len() - 1].trim();
+                let name = if let Some(name) = section.strip_prefix("profile ") {
+                    name.trim()
+                } else {
+                    section
+                };
+                profiles.entry(name.to_string()).or_default();
+                current = Some(name.to_string());
+                continue;
+            }
+            if let (Some(profile), Some(pos)) = (&current, line.find('=')) {
+                let key = line[..pos].trim().to_string();
+                let value = line[pos + 1..].trim().to_string();
+                if let Some(props) = profiles.get_mut(profile) {
+                    props.insert(key, value);
+                }
+            }
+        }
+        Self { profiles }
+    }
+
+    pub fn get_profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    pub fn get(&self, profile: &str, key: &str) -> Option<&str> {
+        self.profiles
+            .get(profile)
+            .and_then(|props| props.get(key))
+            .map(|value| value.as_str())
+    }
+}
+
+/// `AWS_CONFIG_FILE` if set, otherwise `~/.aws/config`
+pub fn config_file_path() -> Option<PathBuf> {
+    match non_empty_env_var(AWS_CONFIG_FILE) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dirs::home_dir().map(|mut path| {
+            path.push(".aws");
+            path.push("config");
+            path
+        }),
+    }
+}
+
+/// `AWS_PROFILE` if set, otherwise `default`
+pub fn default_profile_name() -> String {
+    non_empty_env_var(AWS_PROFILE).unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub fn non_empty_env_var(name: &str) -> Option<String> {
+    match env::var(name) {
+        Ok(value) if !value.is_empty() => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+# comment
+[default]
+region = ap-northeast-1
+
+[profile dev]
+region=us-west-2
+output = json
+; another comment
+[profile   prod  ]
+role_arn = arn:aws:iam::123456789012:role/admin
+";
+
+    #[test]
+    fn can_parse_profiles() {
+        let config = AwsConfig::parse(CONFIG);
+        assert_eq!(Some("ap-northeast-1"), config.get("default", "region"));
+        assert_eq!(Some("us-west-2"), config.get("dev", "region"));
+        assert_eq!(Some("json"), config.get("dev", "output"));
+        assert_eq!(
+            Some("arn:aws:iam::123456789012:role/admin"),
+            config.get("prod", "role_arn"),
+        );
+        assert_eq!(None, config.get("prod", "region"));
+        assert_eq!(None, config.get("unknown", "region"));
+    }
+
+    #[test]
+    fn can_get_profile_names() {
+        let config = AwsConfig::parse(CONFIG);
+        assert_eq!(
+            vec![String::from("default"), String::from("dev"), String::from("prod")],
+            config.get_profile_names(),
+        );
+    }
+
+    #[test]
+    fn can_parse_empty_config() {
+        let config = AwsConfig::parse("");
+        assert_eq!(AwsConfig::default(), config);
+    }
+
+    #[test]
+    fn ignores_properties_outside_of_sections() {
+        let config = AwsConfig::parse("region = us-east-1\n[default]\noutput = text");
+        assert_eq!(None, config.get("default", "region"));
+        assert_eq!(Some("text"), config.get("default", "output"));
+    }
+}
@@ -0,0 +1,196 @@
+use rusoto_core::{Region, HttpClient};
+use rusoto_credential::{
+    AutoRefreshingProvider,
+    AwsCredentials,
+    CredentialsError,
+    DefaultCredentialsProvider,
+    ProfileProvider,
+    ProvideAwsCredentials,
+};
+use rusoto_sts::{AssumeRoleRequest, NewAwsCredsForStsCreds, Sts, StsClient};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use super::config::AwsConfig;
+
+const DEFAULT_SESSION_NAME: &str = "megane";
+
+/// properties of a profile which assumes a role
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleProfile {
+    pub role_arn: String,
+    pub source_profile: Option<String>,
+    pub mfa_serial: Option<String>,
+    pub external_id: Option<String>,
+    pub duration_seconds: Option<i64>,
+    pub role_session_name: Option<String>,
+}
+
+impl RoleProfile {
+    /// returns None if the profile doesn't have `role_arn`
+    pub fn from_config(config: &AwsConfig, profile: &str) -> Option<Self> {
+        let get = |key: &str| config.get(profile, key).map(|v| v.to_string());
+        get("role_arn").map(|role_arn| Self {
+            role_arn,
+            source_profile: get("source_profile"),
+            mfa_serial: get("mfa_serial"),
+            external_id: get("external_id"),
+            duration_seconds: get("duration_seconds").and_then(|v| v.parse().ok()),
+            role_session_name: get("role_session_name"),
+        })
+    }
+}
+
+struct MfaRequest {
+    serial: String,
+    tx: oneshot::Sender<Option<String>>,
+}
+
+/// bridge between the background tasks which need a MFA token code and the UI which asks it
+#[derive(Clone, Default)]
+pub struct MfaPrompt {
+    request: Arc<Mutex<Option<MfaRequest>>>,
+}
+
+impl MfaPrompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ask the UI for the token code of the MFA device and wait for the answer.
+    /// returns None if the user cancels
+    pub async fn ask(&self, serial: &str) -> Option<String> {
+        let (tx, rx) = oneshot::channel();
+        *self.request.lock().unwrap() = Some(MfaRequest {
+            serial: serial.to_string(),
+            tx,
+        });
+        rx.await.ok().flatten()
+    }
+
+    /// serial of the MFA device waiting for the token code
+    pub fn get_pending_serial(&self) -> Option<String> {
+        self.request.lock().unwrap().as_ref().map(|r| r.serial.clone())
+    }
+
+    pub fn answer(&self, token_code: Option<String>) {
+        if let Some(request) = self.request.lock().unwrap().take() {
+            let _ = request.tx.send(token_code);
+        }
+    }
+}
+
+/// provides temporary credentials with `sts:AssumeRole`.
+/// asks the token code through `MfaPrompt` when the role requires MFA
+pub struct AssumeRoleProvider {
+    sts_client: StsClient,
+    role: RoleProfile,
+    prompt: MfaPrompt,
+}
+
+impl AssumeRoleProvider {
+    pub fn new(sts_client: StsClient, role: RoleProfile, prompt: MfaPrompt) -> Self {
+        Self {
+            sts_client,
+            role,
+            prompt,
+        }
+    }
+}
+
+#[async_trait]
+impl ProvideAwsCredentials for AssumeRoleProvider {
+    async fn credentials(&self) -> Result<AwsCredentials, CredentialsError> {
+        let token_code = match &self.role.mfa_serial {
+            Some(serial) => match self.prompt.ask(serial).await {
+                Some(code) => Some(code),
+                None => return Err(CredentialsError::new("MFA token code was not entered")),
+            },
+            None => None,
+        };
+        let request = AssumeRoleRequest {
+            role_arn: self.role.role_arn.clone(),
+            role_session_name: self.role.role_session_name
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SESSION_NAME.to_string()),
+            duration_seconds: self.role.duration_seconds,
+            external_id: self.role.external_id.clone(),
+            serial_number: self.role.mfa_serial.clone(),
+            token_code,
+            ..Default::default()
+        };
+        let response = self.sts_client.assume_role(request).await
+            .map_err(|e| CredentialsError::new(format!("failed to assume role: {}", e)))?;
+        match response.credentials {
+            Some(credentials) => AwsCredentials::new_for_credentials(credentials),
+            None => Err(CredentialsError::new("no credentials in AssumeRole response")),
+        }
+    }
+}
+
+/// build the provider for the role profile.
+/// temporary credentials are cached until they expire
+pub fn assume_role_provider(
+    role: RoleProfile,
+    region: Region,
+    prompt: MfaPrompt,
+) -> Result<AutoRefreshingProvider<AssumeRoleProvider>> {
+    let sts_client = match &role.source_profile {
+        Some(source_profile) => StsClient::new_with(
+            HttpClient::new()?,
+            ProfileProvider::with_default_credentials(source_profile.as_str())?,
+            region,
+        ),
+        None => StsClient::new_with(
+            HttpClient::new()?,
+            DefaultCredentialsProvider::new()?,
+            region,
+        ),
+    };
+    Ok(AutoRefreshingProvider::new(AssumeRoleProvider::new(sts_client, role, prompt))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_role_profile() {
+        let config = AwsConfig::parse("
+[profile prod]
+role_arn = arn:aws:iam::123456789012:role/admin
+source_profile = default
+mfa_serial = arn:aws:iam::000000000000:mfa/user
+duration_seconds = 7200
+[profile dev]
+region = us-east-1
+");
+        let expected = RoleProfile {
+            role_arn: String::from("arn:aws:iam::123456789012:role/admin"),
+            source_profile: Some(String::from("default")),
+            mfa_serial: Some(String::from("arn:aws:iam::000000000000:mfa/user")),
+            external_id: None,
+            duration_seconds: Some(7200),
+            role_session_name: None,
+        };
+        assert_eq!(Some(expected), RoleProfile::from_config(&config, "prod"));
+        assert_eq!(None, RoleProfile::from_config(&config, "dev"));
+    }
+
+    #[tokio::test]
+    async fn prompt_can_pass_token_code() {
+        let prompt = MfaPrompt::new();
+        let prompt0 = prompt.clone();
+        let handle = tokio::spawn(async move {
+            prompt0.ask("serial").await
+        });
+        while prompt.get_pending_serial().is_none() {
+            tokio::time::delay_for(std::time::Duration::from_millis(1)).await;
+        }
+        assert_eq!(Some(String::from("serial")), prompt.get_pending_serial());
+        prompt.answer(Some(String::from("123456")));
+        assert_eq!(Some(String::from("123456")), handle.await.unwrap());
+        assert_eq!(None, prompt.get_pending_serial());
+    }
+}
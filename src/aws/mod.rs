@@ -0,0 +1,141 @@
+use rusoto_core::{Client, Region, HttpClient, RusotoError};
+use rusoto_credential::{DefaultCredentialsProvider, ProfileProvider};
+use rusoto_cloudwatch::CloudWatchClient;
+use rusoto_logs::CloudWatchLogsClient;
+use anyhow::Result;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use config::AwsConfig;
+use credentials::{MfaPrompt, RoleProfile};
+use scheduler::{RequestScheduler, ServiceError};
+
+pub mod config;
+pub mod credentials;
+pub mod region;
+pub mod scheduler;
+
+/// settings which the AWS clients are built from.
+/// shared between the UI and the background tasks
+#[derive(Debug, Clone, PartialEq)]
+pub struct AwsSettings {
+    pub region: Region,
+    /// named profile. the default credential chain is used if none
+    pub profile: Option<String>,
+    /// custom endpoint (e.g. LocalStack) used instead of the AWS endpoints
+    pub endpoint_url: Option<String>,
+}
+
+impl AwsSettings {
+    pub fn new(region: Region, profile: Option<String>, endpoint_url: Option<String>) -> Self {
+        Self {
+            region,
+            profile,
+            endpoint_url,
+        }
+    }
+
+    /// region passed to the clients
+    pub fn client_region(&self) -> Region {
+        region::with_endpoint(&self.region, self.endpoint_url.as_deref())
+    }
+
+    pub fn get_profile_name(&self) -> String {
+        match &self.profile {
+            Some(profile) => profile.clone(),
+            None => config::default_profile_name(),
+        }
+    }
+
+    /// client with the credentials of the profile. the service clients share it,
+    /// so the credentials (and the MFA token code) are cached only once
+    pub fn client(&self, prompt: &MfaPrompt) -> Result<Client> {
+        let role = self.profile
+            .as_ref()
+            .and_then(|profile| RoleProfile::from_config(&AwsConfig::load(), profile));
+        let client = match (&self.profile, role) {
+            (_, Some(role)) => Client::new_with(
+                credentials::assume_role_provider(role, self.client_region(), prompt.clone())?,
+                HttpClient::new()?,
+            ),
+            (Some(profile), None) => Client::new_with(
+                ProfileProvider::with_default_credentials(profile.as_str())?,
+                HttpClient::new()?,
+            ),
+            (None, None) => Client::new_with(
+                DefaultCredentialsProvider::new()?,
+                HttpClient::new()?,
+            ),
+        };
+        Ok(client)
+    }
+
+    pub fn logs_client(&self, prompt: &MfaPrompt) -> Result<CloudWatchLogsClient> {
+        Ok(CloudWatchLogsClient::new_with_client(self.client(prompt)?, self.client_region()))
+    }
+
+    pub fn cloudwatch_client(&self, prompt: &MfaPrompt) -> Result<CloudWatchClient> {
+        Ok(CloudWatchClient::new_with_client(self.client(prompt)?, self.client_region()))
+    }
+}
+
+/// holds an AWS client and rebuilds it whenever the shared settings change.
+/// clones share the same client, so the background tasks share the cached credentials and the scheduler
+#[derive(Clone)]
+pub struct ClientHolder {
+    settings: Arc<Mutex<AwsSettings>>,
+    prompt: MfaPrompt,
+    inner: Arc<Mutex<(AwsSettings, Client)>>,
+    scheduler: RequestScheduler,
+}
+
+impl ClientHolder {
+    pub fn new(settings: Arc<Mutex<AwsSettings>>, prompt: MfaPrompt) -> Result<Self> {
+        let current = settings.lock().unwrap().clone();
+        let client = current.client(&prompt)?;
+        Ok(Self {
+            settings,
+            prompt,
+            inner: Arc::new(Mutex::new((current, client))),
+            scheduler: RequestScheduler::default(),
+        })
+    }
+
+    /// send the request through the scheduler. the API quotas are per account and region
+    pub async fn send<T, E, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        E: ServiceError,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RusotoError<E>>>,
+    {
+        let account = {
+            let settings = self.settings.lock().unwrap();
+            format!("{}/{}", settings.get_profile_name(), settings.region.name())
+        };
+        self.scheduler
+            .run(&account, || {
+                let response = request();
+                async { response.await.map_err(scheduler::from_rusoto_error) }
+            })
+            .await
+    }
+
+    pub fn logs_client(&self) -> Result<CloudWatchLogsClient> {
+        let (client, region) = self.get()?;
+        Ok(CloudWatchLogsClient::new_with_client(client, region))
+    }
+
+    pub fn cloudwatch_client(&self) -> Result<CloudWatchClient> {
+        let (client, region) = self.get()?;
+        Ok(CloudWatchClient::new_with_client(client, region))
+    }
+
+    fn get(&self) -> Result<(Client, Region)> {
+        let latest = self.settings.lock().unwrap().clone();
+        let mut inner = self.inner.lock().unwrap();
+        if latest != inner.0 {
+            let client = latest.client(&self.prompt)?;
+            *inner = (latest, client);
+        }
+        Ok((inner.1.clone(), inner.0.client_region()))
+    }
+}
@@ -0,0 +1,144 @@
+use rusoto_core::Region;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use super::config::{AwsConfig, non_empty_env_var};
+
+/// regions selectable in the region switcher
+pub const REGIONS: [Region; 21] = [
+    Region::ApEast1,
+    Region::ApNortheast1,
+    Region::ApNortheast2,
+    Region::ApNortheast3,
+    Region::ApSouth1,
+    Region::ApSoutheast1,
+    Region::ApSoutheast2,
+    Region::CaCentral1,
+    Region::EuCentral1,
+    Region::EuWest1,
+    Region::EuWest2,
+    Region::EuWest3,
+    Region::EuNorth1,
+    Region::EuSouth1,
+    Region::MeSouth1,
+    Region::SaEast1,
+    Region::UsEast1,
+    Region::UsEast2,
+    Region::UsWest1,
+    Region::UsWest2,
+    Region::AfSouth1,
+];
+
+/// resolve the region in the following order:
+/// 1. `--region` argument
+/// 2. `AWS_REGION`
+/// 3. `AWS_DEFAULT_REGION`
+/// 4. `region` of the profile in the config file
+/// 5. us-east-1
+pub fn resolve_region(region_arg: Option<&str>, config: &AwsConfig, profile: &str) -> Result<Region> {
+    select_region(
+        region_arg,
+        non_empty_env_var("AWS_REGION").as_deref(),
+        non_empty_env_var("AWS_DEFAULT_REGION").as_deref(),
+        config.get(profile, "region"),
+    )
+}
+
+/// resolve the endpoint URL (e.g. LocalStack) in the following order:
+/// 1. `--endpoint-url` argument
+/// 2. `AWS_ENDPOINT_URL`
+/// 3. `endpoint_url` of the profile in the config file
+pub fn resolve_endpoint_url(endpoint_arg: Option<&str>, config: &AwsConfig, profile: &str) -> Option<String> {
+    endpoint_arg
+        .map(|e| e.to_string())
+        .or_else(|| non_empty_env_var("AWS_ENDPOINT_URL"))
+        .or_else(|| config.get(profile, "endpoint_url").map(|e| e.to_string()))
+}
+
+/// region to build the clients with. `Region::Custom` is used if the endpoint is specified
+pub fn with_endpoint(region: &Region, endpoint_url: Option<&str>) -> Region {
+    match endpoint_url {
+        Some(endpoint) => Region::Custom {
+            name: region.name().to_string(),
+            endpoint: endpoint.to_string(),
+        },
+        None => region.clone(),
+    }
+}
+
+fn select_region(
+    region_arg: Option<&str>,
+    env_region: Option<&str>,
+    env_default_region: Option<&str>,
+    profile_region: Option<&str>,
+) -> Result<Region> {
+    let name = region_arg
+        .or(env_region)
+        .or(env_default_region)
+        .or(profile_region);
+    match name {
+        Some(name) => Region::from_str(name).map_err(|e| anyhow!("{}", e)),
+        None => Ok(Region::UsEast1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn argument_has_highest_priority() {
+        let region = select_region(
+            Some("eu-west-1"),
+            Some("us-west-2"),
+            Some("us-east-2"),
+            Some("ap-northeast-1"),
+        ).unwrap();
+        assert_eq!(Region::EuWest1, region);
+    }
+
+    #[test]
+    fn falls_back_in_order() {
+        let region = select_region(None, Some("us-west-2"), Some("us-east-2"), Some("ap-northeast-1")).unwrap();
+        assert_eq!(Region::UsWest2, region);
+        let region = select_region(None, None, Some("us-east-2"), Some("ap-northeast-1")).unwrap();
+        assert_eq!(Region::UsEast2, region);
+        let region = select_region(None, None, None, Some("ap-northeast-1")).unwrap();
+        assert_eq!(Region::ApNortheast1, region);
+        let region = select_region(None, None, None, None).unwrap();
+        assert_eq!(Region::UsEast1, region);
+    }
+
+    #[test]
+    fn invalid_region_is_error() {
+        assert!(select_region(Some("mars-north-1"), None, None, None).is_err());
+    }
+
+    #[test]
+    fn can_resolve_endpoint_url() {
+        let config = AwsConfig::parse("[profile local]\nendpoint_url = http://localhost:4566");
+        assert_eq!(
+            Some(String::from("http://localhost:9999")),
+            resolve_endpoint_url(Some("http://localhost:9999"), &config, "local"),
+        );
+    }
+
+    #[test]
+    fn can_build_custom_region() {
+        let region = with_endpoint(&Region::ApNortheast1, Some("http://localhost:4566"));
+        assert_eq!(
+            Region::Custom {
+                name: String::from("ap-northeast-1"),
+                endpoint: String::from("http://localhost:4566"),
+            },
+            region,
+        );
+        assert_eq!(Region::ApNortheast1, with_endpoint(&Region::ApNortheast1, None));
+    }
+
+    #[test]
+    fn can_resolve_region_from_profile() {
+        let config = AwsConfig::parse("[profile dev]\nregion = eu-north-1");
+        let region = resolve_region(Some("sa-east-1"), &config, "dev").unwrap();
+        assert_eq!(Region::SaEast1, region);
+    }
+}
@@ -0,0 +1,355 @@
+use rusoto_core::RusotoError;
+use rusoto_cloudwatch::{DescribeAlarmHistoryError, DescribeAlarmsError, GetMetricDataError, ListMetricsError};
+use rusoto_logs::{
+    DescribeLogGroupsError,
+    DescribeLogStreamsError,
+    FilterLogEventsError,
+    GetLogEventsError,
+    GetQueryResultsError,
+    StartQueryError,
+    StopQueryError,
+};
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// max number of the requests of an account which are sent at the same time
+pub const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// error of a request which may succeed when it's sent again
+#[derive(Debug)]
+pub struct TransientError {
+    /// the request was rejected by the rate limit of the API
+    pub throttled: bool,
+    message: String,
+}
+
+impl fmt::Display for TransientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for TransientError {}
+
+impl TransientError {
+    pub fn new(message: &str, throttled: bool) -> Self {
+        Self {
+            throttled,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// whether the request should be retried for the error
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<TransientError>().is_some()
+}
+
+/// service error of a request
+pub trait ServiceError: Error + Send + Sync + 'static {
+    /// the service is temporarily unavailable
+    fn is_unavailable(&self) -> bool;
+}
+
+macro_rules! impl_service_unavailable {
+    ($($error:ident),*) => {
+        $(
+            impl ServiceError for $error {
+                fn is_unavailable(&self) -> bool {
+                    matches!(self, $error::ServiceUnavailable(_))
+                }
+            }
+        )*
+    };
+}
+
+impl_service_unavailable!(
+    DescribeLogGroupsError,
+    DescribeLogStreamsError,
+    FilterLogEventsError,
+    GetLogEventsError,
+    GetQueryResultsError,
+    StartQueryError,
+    StopQueryError
+);
+
+impl ServiceError for ListMetricsError {
+    fn is_unavailable(&self) -> bool {
+        matches!(self, ListMetricsError::InternalServiceFault(_))
+    }
+}
+
+impl ServiceError for GetMetricDataError {
+    fn is_unavailable(&self) -> bool {
+        false
+    }
+}
+
+impl ServiceError for DescribeAlarmsError {
+    fn is_unavailable(&self) -> bool {
+        false
+    }
+}
+
+impl ServiceError for DescribeAlarmHistoryError {
+    fn is_unavailable(&self) -> bool {
+        false
+    }
+}
+
+/// error codes of the rate limits of CloudWatch and CloudWatch Logs
+const THROTTLING_CODES: [&str; 4] = ["ThrottlingException", "Throttling", "TooManyRequestsException", "RequestLimitExceeded"];
+
+/// error code of the body of an error response. CloudWatch Logs answers
+/// `{"__type": "..."}` in JSON, and CloudWatch answers `<Code>...</Code>` in XML
+fn get_error_code(body: &str) -> Option<String> {
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
+        let code = json.get("__type")?.as_str()?;
+        // the type may be qualified like "com.amazonaws.logs#ThrottlingException"
+        return Some(code.rsplit('#').next().unwrap_or(code).to_string());
+    }
+    let start = body.find("<Code>")? + "<Code>".len();
+    let end = body[start..].find("</Code>")? + start;
+    Some(body[start..end].trim().to_string())
+}
+
+/// the errors of the rate limits, the network and the server side are transient
+pub fn from_rusoto_error<E: ServiceError>(error: RusotoError<E>) -> anyhow::Error {
+    let message = error.to_string();
+    match &error {
+        RusotoError::Unknown(res) => {
+            let is_throttled = res.status.as_u16() == 429 || get_error_code(res.body_as_str())
+                .is_some_and(|code| THROTTLING_CODES.contains(&code.as_str()));
+            if is_throttled {
+                TransientError::new(&message, true).into()
+            } else if res.status.is_server_error() {
+                TransientError::new(&message, false).into()
+            } else {
+                error.into()
+            }
+        },
+        RusotoError::HttpDispatch(_) => TransientError::new(&message, false).into(),
+        RusotoError::Service(e) if e.is_unavailable() => TransientError::new(&message, false).into(),
+        _ => error.into(),
+    }
+}
+
+/// how the transient errors are retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// number of the attempts including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// exponential backoff before the retry. `jitter` (0.0 to 1.0) picks the delay between the half and the whole
+    pub fn get_delay(&self, retry: u32, jitter: f64) -> Duration {
+        let delay = self.base_delay
+            .checked_mul(1 << retry.min(16))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        delay / 2 + delay.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+    }
+}
+
+/// sends the requests of the background tasks.
+/// the requests are limited per account and retried with backoff on the transient errors.
+/// clones share the limits
+#[derive(Debug, Clone)]
+pub struct RequestScheduler {
+    policy: RetryPolicy,
+    max_concurrent_requests: usize,
+    semaphores: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl Default for RequestScheduler {
+    fn default() -> Self {
+        Self::new(RetryPolicy::default(), MAX_CONCURRENT_REQUESTS)
+    }
+}
+
+impl RequestScheduler {
+    pub fn new(policy: RetryPolicy, max_concurrent_requests: usize) -> Self {
+        Self {
+            policy,
+            max_concurrent_requests,
+            semaphores: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn get_semaphore(&self, account: &str) -> Arc<Semaphore> {
+        let mut semaphores = self.semaphores.lock().unwrap();
+        let max_concurrent_requests = self.max_concurrent_requests;
+        Arc::clone(
+            semaphores
+                .entry(account.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(max_concurrent_requests)))
+        )
+    }
+
+    /// send the request of the account. the slot of the account is released while waiting for a retry
+    pub async fn run<T, F, Fut>(&self, account: &str, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let semaphore = self.get_semaphore(account);
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let result = {
+                let _permit = semaphore.acquire().await;
+                request().await
+            };
+            match result {
+                Ok(res) => return Ok(res),
+                Err(e) if is_transient(&e) && attempts < self.policy.max_attempts => {
+                    let delay = self.policy.get_delay(attempts - 1, rand::thread_rng().gen());
+                    tokio::time::delay_for(delay).await;
+                },
+                Err(e) if attempts > 1 => return Err(anyhow!("{} (gave up after {} attempts)", e, attempts)),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_core::request::BufferedHttpResponse;
+    use rusoto_logs::FilterLogEventsError;
+    use std::convert::TryInto;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn make_scheduler(max_concurrent_requests: usize) -> RequestScheduler {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        RequestScheduler::new(policy, max_concurrent_requests)
+    }
+
+    fn make_response(status: u16, body: &str) -> RusotoError<FilterLogEventsError> {
+        RusotoError::Unknown(BufferedHttpResponse {
+            status: status.try_into().unwrap(),
+            body: body.to_string().into(),
+            headers: Default::default(),
+        })
+    }
+
+    #[test]
+    fn can_classify_rusoto_errors() {
+        let throttled = from_rusoto_error(make_response(400, r#"{"__type":"ThrottlingException","message":"Rate exceeded"}"#));
+        assert!(throttled.downcast_ref::<TransientError>().unwrap().throttled);
+        let unavailable = from_rusoto_error(make_response(503, ""));
+        assert!(!unavailable.downcast_ref::<TransientError>().unwrap().throttled);
+        let service = from_rusoto_error(RusotoError::Service(FilterLogEventsError::ServiceUnavailable(String::from("down"))));
+        assert!(is_transient(&service));
+        assert!(!is_transient(&from_rusoto_error(make_response(400, r#"{"__type":"InvalidParameterException"}"#))));
+        // the code is compared exactly, not found in the message
+        assert!(!is_transient(&from_rusoto_error(make_response(
+            400,
+            r#"{"__type":"InvalidParameterException","message":"not a ThrottlingException"}"#,
+        ))));
+        let qualified = from_rusoto_error(make_response(400, r#"{"__type":"com.amazonaws.logs#ThrottlingException"}"#));
+        assert!(qualified.downcast_ref::<TransientError>().unwrap().throttled);
+        let xml = "<ErrorResponse><Error><Type>Sender</Type><Code>Throttling</Code><Message>Rate exceeded</Message></Error></ErrorResponse>";
+        let throttled: RusotoError<ListMetricsError> = RusotoError::Unknown(BufferedHttpResponse {
+            status: 400u16.try_into().unwrap(),
+            body: xml.to_string().into(),
+            headers: Default::default(),
+        });
+        assert!(from_rusoto_error(throttled).downcast_ref::<TransientError>().unwrap().throttled);
+        assert!(!is_transient(&from_rusoto_error(
+            RusotoError::Service(FilterLogEventsError::ResourceNotFound(String::from("group")))
+        )));
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_max() {
+        let policy = RetryPolicy::default();
+        assert_eq!(Duration::from_millis(100), policy.get_delay(0, 0.0));
+        assert_eq!(Duration::from_millis(200), policy.get_delay(0, 1.0));
+        assert_eq!(Duration::from_millis(800), policy.get_delay(2, 1.0));
+        assert_eq!(Duration::from_secs(10), policy.get_delay(10, 1.0));
+        assert_eq!(Duration::from_secs(10), policy.get_delay(100, 1.0));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_limit() {
+        let scheduler = make_scheduler(1);
+        let calls = AtomicUsize::new(0);
+        let result = scheduler.run("account", || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(TransientError::new("Rate exceeded", true).into())
+            } else {
+                Ok(1)
+            }
+        }).await;
+        assert_eq!(1, result.unwrap());
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<()> = scheduler.run("account", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(TransientError::new("Rate exceeded", true).into())
+        }).await;
+        assert_eq!("Rate exceeded (gave up after 3 attempts)", result.unwrap_err().to_string());
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<()> = scheduler.run("account", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow!("AccessDeniedException"))
+        }).await;
+        assert_eq!("AccessDeniedException", result.unwrap_err().to_string());
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn limits_concurrent_requests_per_account() {
+        let scheduler = make_scheduler(2);
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let request = |account: &'static str| {
+            let scheduler = scheduler.clone();
+            let running = &running;
+            let max_running = &max_running;
+            async move {
+                scheduler.run(account, || async {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::delay_for(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }).await
+            }
+        };
+        futures::future::join_all((0..6).map(|_| request("a"))).await;
+        assert_eq!(2, max_running.load(Ordering::SeqCst));
+        max_running.store(0, Ordering::SeqCst);
+        futures::future::join_all((0..6).map(|i| request(if i % 2 == 0 { "a" } else { "b" }))).await;
+        assert_eq!(4, max_running.load(Ordering::SeqCst));
+    }
+}
@@ -7,29 +7,38 @@ use crossterm::{
 };
 use std::{
     io::{stdout, Write},
-    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant},
     sync::{mpsc, Arc, Mutex},
     thread,
 };
 use clap::{
     crate_authors, crate_description, crate_name, crate_version,
-    App as ClapApp,
+    App as ClapApp, Arg, ArgMatches, SubCommand,
 };
 use tui::{
     backend::CrosstermBackend,
     Terminal,
 };
 use anyhow::Result;
-use rusoto_core::Region;
-use rusoto_logs::{
-    CloudWatchLogs,
-    CloudWatchLogsClient,
-    DescribeLogGroupsRequest,
-    FilterLogEventsRequest,
-};
 
 use megane::{ui, app::App, instruction::Instruction, globalstate::{GlobalState
-, GlobalStateTail}};
+, GlobalStateMetrics, GlobalStateTail}};
+use megane::handler::{InstructionHandler, TailHandler, now_millis};
+use megane::logsource::rusoto::RusotoLogSource;
+use megane::metricsource::rusoto::RusotoMetricSource;
+use megane::cli::{self, OutputFormat, groups::GroupsOptions, search::SearchOptions, tail::TailOptions};
+use megane::tail::TailSettings;
+use megane::utils::{self, timestamp_format::TimestampFormat};
+use megane::aws::{
+    AwsSettings,
+    ClientHolder,
+    config::{self, AwsConfig},
+    credentials::MfaPrompt,
+    region,
+};
+
+/// how often the tail task checks whether a poll is due
+const TAIL_SCHEDULE_STEP: Duration = Duration::from_millis(100);
 
 enum Event<I> {
     Input(I),
@@ -39,12 +48,257 @@ enum Event<I> {
 #[tokio::main]
 async fn main() -> Result<()> {
     // setup app
-    let _clap = ClapApp::new(crate_name!())
+    let clap = ClapApp::new(crate_name!())
         .author(crate_authors!())
         .version(crate_version!())
         .about(crate_description!())
+        .arg(
+            Arg::with_name("region")
+                .long("region")
+                .value_name("REGION")
+                .global(true)
+                .help("AWS region (default: AWS_REGION, AWS_DEFAULT_REGION or the region of the profile)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("PROFILE")
+                .global(true)
+                .help("AWS named profile (default: AWS_PROFILE or the default credential chain)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("endpoint-url")
+                .long("endpoint-url")
+                .value_name("URL")
+                .global(true)
+                .help("Custom endpoint URL such as LocalStack (default: AWS_ENDPOINT_URL or endpoint_url of the profile)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("timezone")
+                .long("timezone")
+                .value_name("ZONE")
+                .global(true)
+                .help("Timezone of the timestamps: utc, local or an IANA name like Asia/Tokyo")
+                .default_value("utc")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("time-format")
+                .long("time-format")
+                .value_name("FORMAT")
+                .global(true)
+                .help("strftime format of the timestamps")
+                .default_value("%Y-%m-%d %H:%M:%S %Z")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("millis")
+                .long("millis")
+                .global(true)
+                .help("Show the milliseconds of the timestamps")
+        )
+        .arg(
+            Arg::with_name("relative-time")
+                .long("relative-time")
+                .global(true)
+                .help("Show the timestamps as elapsed time like \"3m ago\" (toggled with 't' in the logs view)")
+        )
+        .arg(
+            Arg::with_name("tail-interval")
+                .long("tail-interval")
+                .value_name("DURATION")
+                .global(true)
+                .help("Interval between the polls of tail (e.g. 500ms, 5s) (default: megane_tail_interval of the profile or 1s)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("tail-look-back")
+                .long("tail-look-back")
+                .value_name("DURATION")
+                .global(true)
+                .help("How far each poll of tail looks back for late events (default: megane_tail_look_back of the profile or 60s)")
+                .takes_value(true)
+        )
+        .subcommand(
+            SubCommand::with_name("tail")
+                .about("Print new log events of the log group to stdout")
+                .arg(
+                    Arg::with_name("group")
+                        .value_name("LOG_GROUP")
+                        .help("Log group name")
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .value_name("PATTERN")
+                        .help("Filter pattern")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("TIME")
+                        .help("Print events since this time at first (e.g. 30s, 5m, -1h, today 09:00)")
+                        .default_value("1m")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FORMAT")
+                        .help("Output format. json and ndjson both write one JSON object per line")
+                        .possible_values(&["plain", "json", "ndjson", "csv"])
+                        .default_value("plain")
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Print all log events of the log group matching the filter to stdout")
+                .arg(
+                    Arg::with_name("group")
+                        .value_name("LOG_GROUP")
+                        .help("Log group name")
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .value_name("PATTERN")
+                        .help("Filter pattern")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("TIME")
+                        .help("Search events since this time (e.g. 5m, now-2d, yesterday 09:00, last monday)")
+                        .conflicts_with("start")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .value_name("TIME")
+                        .help("Search events until this time (e.g. -1h, today)")
+                        .conflicts_with("end")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("start")
+                        .long("start")
+                        .value_name("TIME")
+                        .help("Start time in epoch millis, RFC 3339 (e.g. 2020-12-12T01:01:00Z) or local time (e.g. 2020-12-12-01:01:00)")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .long("end")
+                        .value_name("TIME")
+                        .help("End time in the same formats as --start (default: now)")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("max-events")
+                        .long("max-events")
+                        .value_name("N")
+                        .help("Stop after printing N events")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FORMAT")
+                        .help("Output format")
+                        .possible_values(&["plain", "json", "ndjson", "csv"])
+                        .default_value("plain")
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("groups")
+                .about("Print log groups to stdout")
+                .arg(
+                    Arg::with_name("prefix")
+                        .long("prefix")
+                        .value_name("PREFIX")
+                        .help("Log group name prefix")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .value_name("KEY")
+                        .help("Sort by name, stored bytes (largest first) or creation time (newest first)")
+                        .possible_values(&["name", "size", "created"])
+                        .default_value("name")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FORMAT")
+                        .help("Output format")
+                        .possible_values(&["table", "json"])
+                        .default_value("table")
+                        .takes_value(true)
+                )
+        )
         .get_matches();
 
+    // the format is validated because chrono panics while drawing an invalid one
+    let timestamp_format = TimestampFormat {
+        zone: clap.value_of("timezone").unwrap_or("utc").parse()?,
+        format: clap.value_of("time-format").unwrap_or_default().to_string(),
+        millis: clap.is_present("millis"),
+        relative: clap.is_present("relative-time"),
+    };
+    timestamp_format.validate()?;
+
+    // resolve AWS settings
+    let aws_config = AwsConfig::load();
+    let profile = clap.value_of("profile")
+        .map(|p| p.to_string())
+        .or_else(|| config::non_empty_env_var("AWS_PROFILE"));
+    let profile_name = profile.clone().unwrap_or_else(config::default_profile_name);
+    let region = region::resolve_region(
+        clap.value_of("region"),
+        &aws_config,
+        &profile_name,
+    )?;
+    let endpoint_url = region::resolve_endpoint_url(
+        clap.value_of("endpoint-url"),
+        &aws_config,
+        &profile_name,
+    );
+    let tail_settings = TailSettings::resolve(
+        clap.value_of("tail-interval"),
+        clap.value_of("tail-look-back"),
+        &aws_config,
+        &profile_name,
+    )?;
+    let settings = Arc::new(Mutex::new(AwsSettings::new(region, profile, endpoint_url)));
+    let mfa_prompt = MfaPrompt::new();
+    let client_holder = ClientHolder::new(Arc::clone(&settings), mfa_prompt.clone())?;
+
+    // non-interactive commands
+    if let Some(matches) = clap.subcommand_matches("tail") {
+        cli::spawn_mfa_prompt_reader(mfa_prompt);
+        return run_tail(RusotoLogSource::new(client_holder), matches, tail_settings, timestamp_format).await;
+    }
+    if let Some(matches) = clap.subcommand_matches("search") {
+        cli::spawn_mfa_prompt_reader(mfa_prompt);
+        return run_search(RusotoLogSource::new(client_holder), matches, timestamp_format).await;
+    }
+    if let Some(matches) = clap.subcommand_matches("groups") {
+        cli::spawn_mfa_prompt_reader(mfa_prompt);
+        return run_groups(RusotoLogSource::new(client_holder), matches).await;
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = stdout();
@@ -54,7 +308,6 @@ async fn main() -> Result<()> {
 
     // input handling
     let (tx, rx) = mpsc::channel();
-    let (tail_tx, tail_rx) = mpsc::channel::<i64>();
     let tick_rate = Duration::from_millis(1000);
     thread::spawn(move || {
         let mut last_tick = Instant::now();
@@ -66,7 +319,6 @@ async fn main() -> Result<()> {
             }
             if last_tick.elapsed() >= tick_rate {
                 tx.send(Event::Tick).unwrap();
-                tail_tx.send(0).unwrap();
                 last_tick = Instant::now();
             }
         }
@@ -76,118 +328,38 @@ async fn main() -> Result<()> {
     let (aws_tx, aws_rx) = mpsc::channel::<Instruction>();
     let state = Arc::new(Mutex::new(GlobalState::new()));
     let state0 = Arc::clone(&state);
+    let metrics_state = Arc::new(Mutex::new(GlobalStateMetrics::new()));
+    let metrics_state0 = Arc::clone(&metrics_state);
+    let tail_client_holder = client_holder.clone();
+    let handler = InstructionHandler::new(
+        RusotoLogSource::new(client_holder.clone()),
+        RusotoMetricSource::new(client_holder),
+        state0,
+        metrics_state0,
+    );
     tokio::spawn(async move {
-        let client = CloudWatchLogsClient::new(Region::ApNortheast1);
         loop {
             let instruction = aws_rx.recv().unwrap();
-            match instruction {
-                Instruction::FetchLogEvents(log_group_name, filter_pattern, start, end) => {
-                    let start = if start == 0 {
-                        None
-                    } else {
-                        Some(start)
-                    };
-                    let end = if end == 0 {
-                        None
-                    } else {
-                        Some(end)
-                    };
-                    state0.lock().unwrap().log_events_fetching = true;
-                    if log_group_name != state0.lock().unwrap().log_events_selected_log_group_name {
-                        state0.lock().unwrap().log_events.clear_items();
-                    }
-                    let mut request = FilterLogEventsRequest::default();
-                    request.log_group_name = log_group_name.clone();
-                    request.filter_pattern = Some(filter_pattern);
-                    request.next_token = state0.lock().unwrap().log_events_next_token.clone();
-                    request.start_time = start;
-                    request.end_time = end;
-                    request.limit = Some(10);
-                    let response = client.filter_log_events(request).await;
-                    if let Ok(mut res) = response {
-                        let mut state = state0.lock().unwrap();
-                        state.log_events_selected_log_group_name = log_group_name;
-                        state.log_events_next_token = res.next_token.clone();
-                        let mut empty = vec![];
-                        let mut events = match &mut res.events {
-                            Some(events) => {
-                                // events.reverse();
-                                events
-                            },
-                            None => &mut empty,
-                        };
-                        let token = state.log_events_next_token.clone();
-                        state.log_events.push_items(&mut events, token.as_ref());
-                    }
-                    state0.lock().unwrap().log_events_fetching = false;
-                },
-                Instruction::FetchLogGroups => {
-                    state0.lock().unwrap().log_groups_fething = true;
-                    loop {
-                        let request = DescribeLogGroupsRequest {
-                            limit: Some(50),
-                            log_group_name_prefix: None,
-                            next_token: state0.lock().unwrap().log_groups_next_token.clone(),
-                        };
-                        let response = client.describe_log_groups(request).await;
-                        if let Ok(res) = response {
-                            let mut state = state0.lock().unwrap();
-                            state.log_groups_next_token = res.next_token;
-                            let mut log_groups = match res.log_groups {
-                                Some(log_groups) => log_groups,
-                                None => vec![],
-                            };
-                            let token = state.log_groups_next_token.clone();
-                            state.log_groups.push_items(&mut log_groups, token.as_ref());
-                            if let None = token {
-                                break;
-                            }
-                        }
-                    }
-                    state0.lock().unwrap().log_groups_fething = false;
-                },
-            }
+            handler.handle(instruction).await;
         }
     });
 
     // tail mode thread
+    // polls at the interval of the settings while the logs view is in tail mode
     let state_tail = Arc::new(Mutex::new(GlobalStateTail::new()));
+    state_tail.lock().unwrap().settings = tail_settings;
     let state_tail0 = Arc::clone(&state_tail);
+    let mut tail_handler = TailHandler::new(RusotoLogSource::new(tail_client_holder), state_tail0);
     tokio::spawn(async move {
-        let client = CloudWatchLogsClient::new(Region::ApNortheast1);
         loop {
-            let _ = tail_rx.recv().unwrap();
-            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-            let start = now
-                .checked_sub(Duration::from_secs(60))
-                .unwrap_or(Duration::from_secs(0))
-                .as_millis();
-            state_tail0.lock().unwrap().log_events_fetching = true;
-            let mut request = FilterLogEventsRequest::default();
-            request.log_group_name = state_tail0.lock().unwrap().log_events_selected_log_group_name.clone();
-            request.filter_pattern = state_tail0.lock().unwrap().log_events_filter_pattern.clone();
-            request.next_token = state_tail0.lock().unwrap().log_events_next_token.clone();
-            request.start_time = Some(start as i64);
-            request.end_time = Some(now.as_millis() as i64);
-            request.limit = Some(100);
-            let response = client.filter_log_events(request).await;
-            if let Ok(mut res) = response {
-                state_tail0.lock().unwrap().log_events_next_token = res.next_token.clone();
-                let mut empty = vec![];
-                let mut events = match &mut res.events {
-                    Some(events) => {
-                        events
-                    },
-                    None => &mut empty,
-                };
-                let token = state_tail0.lock().unwrap().log_events_next_token.clone();
-                state_tail0.lock().unwrap().log_events.push_items(&mut events, token.as_ref());
+            if tail_handler.is_due() {
+                tail_handler.poll().await;
             }
-            state_tail0.lock().unwrap().log_events_fetching = false;
+            tokio::time::delay_for(TAIL_SCHEDULE_STEP).await;
         }
     });
 
-    let mut app = App::new(aws_tx, state, state_tail).await?; 
+    let mut app = App::new(aws_tx, state, state_tail, metrics_state, settings, mfa_prompt, timestamp_format).await?;
 
     terminal.clear()?;
 
@@ -209,10 +381,58 @@ async fn main() -> Result<()> {
                 }
                 _ => app.handle_event(event).await
             },
-            Event::Tick => {
-                // TODO: fetch logs and metrics
-            }
+            Event::Tick => app.on_tick(),
         }
     }
     Ok(())
 }
+
+async fn run_tail(source: RusotoLogSource, matches: &ArgMatches<'_>, settings: TailSettings, timestamp_format: TimestampFormat) -> Result<()> {
+    let options = TailOptions {
+        log_group_name: matches.value_of("group").unwrap_or_default().to_string(),
+        filter_pattern: matches.value_of("filter").map(|f| f.to_string()),
+        since: Duration::from_millis(
+            (now_millis() - utils::parse_time(matches.value_of("since").unwrap_or("1m"))?).max(0) as u64
+        ),
+        interval: settings.interval,
+        look_back: settings.look_back,
+        format: matches.value_of("output").unwrap_or("plain").parse::<OutputFormat>()?,
+        timestamp_format,
+    };
+    cli::tail::run(source, options, &mut stdout()).await
+}
+
+async fn run_search(source: RusotoLogSource, matches: &ArgMatches<'_>, timestamp_format: TimestampFormat) -> Result<()> {
+    let start = match matches.value_of("since").or_else(|| matches.value_of("start")) {
+        Some(start) => utils::parse_time(start)?,
+        None => 0,
+    };
+    let end = match matches.value_of("until").or_else(|| matches.value_of("end")) {
+        Some(end) => utils::parse_time(end)?,
+        None => 0,
+    };
+    let max_events = match matches.value_of("max-events") {
+        Some(max_events) => Some(max_events.parse::<usize>()?),
+        None => None,
+    };
+    let options = SearchOptions {
+        log_group_name: matches.value_of("group").unwrap_or_default().to_string(),
+        filter_pattern: matches.value_of("filter").map(|f| f.to_string()),
+        start,
+        end,
+        max_events,
+        format: matches.value_of("output").unwrap_or("plain").parse::<OutputFormat>()?,
+        timestamp_format,
+    };
+    cli::search::run(source, options, &mut stdout()).await?;
+    Ok(())
+}
+
+async fn run_groups(source: RusotoLogSource, matches: &ArgMatches<'_>) -> Result<()> {
+    let options = GroupsOptions {
+        prefix: matches.value_of("prefix").map(|p| p.to_string()),
+        sort: matches.value_of("sort").unwrap_or("name").parse()?,
+        format: matches.value_of("output").unwrap_or("table").parse()?,
+    };
+    cli::groups::run(source, options, &mut stdout()).await
+}
@@ -0,0 +1,229 @@
+use rusoto_logs::{DescribeLogGroupsRequest, LogGroup};
+use anyhow::{anyhow, Result};
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use std::io::Write;
+use std::str::FromStr;
+use crate::logsource::LogSource;
+
+/// output format of `groups` command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupsFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for GroupsFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(GroupsFormat::Table),
+            "json" => Ok(GroupsFormat::Json),
+            _ => Err(anyhow!("unknown output format: {}", s)),
+        }
+    }
+}
+
+/// sort order of `groups` command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupsSort {
+    /// by name ascending
+    Name,
+    /// by stored bytes descending
+    Size,
+    /// by creation time descending
+    Created,
+}
+
+impl FromStr for GroupsSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "name" => Ok(GroupsSort::Name),
+            "size" => Ok(GroupsSort::Size),
+            "created" => Ok(GroupsSort::Created),
+            _ => Err(anyhow!("unknown sort key: {}", s)),
+        }
+    }
+}
+
+pub struct GroupsOptions {
+    pub prefix: Option<String>,
+    pub sort: GroupsSort,
+    pub format: GroupsFormat,
+}
+
+/// fetch all log groups whose names start with `prefix`
+pub async fn fetch_log_groups<S: LogSource>(source: &S, prefix: Option<String>) -> Result<Vec<LogGroup>> {
+    let mut log_groups = vec![];
+    let mut next_token = None;
+    loop {
+        let request = DescribeLogGroupsRequest {
+            limit: Some(50),
+            log_group_name_prefix: prefix.clone(),
+            next_token,
+        };
+        let response = source.describe_log_groups(request).await?;
+        log_groups.append(&mut response.log_groups.unwrap_or_default());
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(log_groups)
+}
+
+pub fn sort_log_groups(log_groups: &mut [LogGroup], sort: GroupsSort) {
+    match sort {
+        GroupsSort::Name => log_groups.sort_by(|a, b| a.log_group_name.cmp(&b.log_group_name)),
+        GroupsSort::Size => log_groups.sort_by_key(|g| std::cmp::Reverse(g.stored_bytes)),
+        GroupsSort::Created => log_groups.sort_by_key(|g| std::cmp::Reverse(g.creation_time)),
+    }
+}
+
+fn format_creation_time(log_group: &LogGroup) -> String {
+    match log_group.creation_time {
+        Some(time) => Utc.timestamp(time / 1000, 0).format("%Y-%m-%d %H:%M:%S %Z").to_string(),
+        None => String::from("-"),
+    }
+}
+
+fn format_optional(value: Option<i64>, none: &str) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => none.to_string(),
+    }
+}
+
+fn format_table(log_groups: &[LogGroup]) -> String {
+    let header = [
+        String::from("NAME"),
+        String::from("RETENTION"),
+        String::from("STORED BYTES"),
+        String::from("METRIC FILTERS"),
+        String::from("CREATED"),
+    ];
+    let mut rows = vec![header];
+    for log_group in log_groups {
+        rows.push([
+            log_group.log_group_name.clone().unwrap_or_default(),
+            format_optional(log_group.retention_in_days, "never"),
+            format_optional(log_group.stored_bytes, "-"),
+            format_optional(log_group.metric_filter_count, "-"),
+            format_creation_time(log_group),
+        ]);
+    }
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in &rows {
+        let line = format!(
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {}",
+            row[0], row[1], row[2], row[3], row[4],
+            w0 = widths[0], w1 = widths[1], w2 = widths[2], w3 = widths[3],
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn format_json(log_groups: &[LogGroup]) -> String {
+    let values: Vec<serde_json::Value> = log_groups
+        .iter()
+        .map(|g| json!({
+            "logGroupName": g.log_group_name,
+            "retentionInDays": g.retention_in_days,
+            "storedBytes": g.stored_bytes,
+            "metricFilterCount": g.metric_filter_count,
+            "creationTime": g.creation_time,
+            "arn": g.arn,
+        }))
+        .collect();
+    format!("{}\n", serde_json::to_string_pretty(&values).unwrap_or_default())
+}
+
+/// print the log groups
+pub async fn run<S: LogSource, W: Write>(source: S, options: GroupsOptions, out: &mut W) -> Result<()> {
+    let mut log_groups = fetch_log_groups(&source, options.prefix).await?;
+    sort_log_groups(&mut log_groups, options.sort);
+    let output = match options.format {
+        GroupsFormat::Table => format_table(&log_groups),
+        GroupsFormat::Json => format_json(&log_groups),
+    };
+    super::write_str(out, &output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logsource::memory::InMemoryLogSource;
+
+    fn make_log_group(name: &str, stored_bytes: i64, creation_time: i64) -> LogGroup {
+        LogGroup {
+            log_group_name: Some(name.to_string()),
+            stored_bytes: Some(stored_bytes),
+            creation_time: Some(creation_time),
+            metric_filter_count: Some(0),
+            ..Default::default()
+        }
+    }
+
+    fn make_source() -> InMemoryLogSource {
+        let source = InMemoryLogSource::new(2);
+        source.insert_log_group(make_log_group("/aws/lambda/b", 100, 1577836800000));
+        source.insert_log_group(make_log_group("/aws/lambda/a", 300, 1577836700000));
+        source.insert_log_group(make_log_group("/ecs/c", 200, 1577836900000));
+        source
+    }
+
+    fn names(log_groups: &[LogGroup]) -> Vec<&str> {
+        log_groups.iter().map(|g| g.log_group_name.as_deref().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn fetches_all_pages_with_prefix() {
+        let source = make_source();
+        assert_eq!(3, fetch_log_groups(&source, None).await.unwrap().len());
+        let log_groups = fetch_log_groups(&source, Some(String::from("/aws/"))).await.unwrap();
+        assert_eq!(vec!["/aws/lambda/b", "/aws/lambda/a"], names(&log_groups));
+    }
+
+    #[tokio::test]
+    async fn can_sort_log_groups() {
+        let mut log_groups = fetch_log_groups(&make_source(), None).await.unwrap();
+        sort_log_groups(&mut log_groups, GroupsSort::Name);
+        assert_eq!(vec!["/aws/lambda/a", "/aws/lambda/b", "/ecs/c"], names(&log_groups));
+        sort_log_groups(&mut log_groups, GroupsSort::Size);
+        assert_eq!(vec!["/aws/lambda/a", "/ecs/c", "/aws/lambda/b"], names(&log_groups));
+        sort_log_groups(&mut log_groups, GroupsSort::Created);
+        assert_eq!(vec!["/ecs/c", "/aws/lambda/b", "/aws/lambda/a"], names(&log_groups));
+    }
+
+    #[test]
+    fn can_format_table() {
+        let mut log_group = make_log_group("/ecs/c", 200, 1577836800000);
+        log_group.retention_in_days = Some(30);
+        let table = format_table(&[log_group, make_log_group("/aws/lambda/a", 3000, 1577836800000)]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!("NAME           RETENTION  STORED BYTES  METRIC FILTERS  CREATED", lines[0]);
+        assert_eq!("/ecs/c                30           200               0  2020-01-01 00:00:00 UTC", lines[1]);
+        assert_eq!("/aws/lambda/a      never          3000               0  2020-01-01 00:00:00 UTC", lines[2]);
+    }
+
+    #[test]
+    fn can_format_json() {
+        let output = format_json(&[make_log_group("/ecs/c", 200, 1577836800000)]);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!("/ecs/c", value[0]["logGroupName"]);
+        assert_eq!(200, value[0]["storedBytes"]);
+        assert!(value[0]["retentionInDays"].is_null());
+    }
+}
@@ -0,0 +1,263 @@
+use rusoto_logs::FilteredLogEvent;
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::aws::credentials::MfaPrompt;
+use crate::utils::timestamp_format::TimestampFormat;
+
+pub mod groups;
+pub mod search;
+pub mod tail;
+
+/// output format of the non-interactive commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Plain,
+    /// JSON array
+    Json,
+    /// one JSON object per line
+    Ndjson,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "plain" => Ok(OutputFormat::Plain),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(anyhow!("unknown output format: {}", s)),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// format of the output which never ends like tail. an array can't be closed,
+    /// so json is written as one JSON object per line as before `ndjson` was added
+    pub fn for_stream(self) -> Self {
+        match self {
+            OutputFormat::Json => OutputFormat::Ndjson,
+            format => format,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "timestamp,ingestion_time,log_stream_name,event_id,message";
+
+fn event_to_json(event: &FilteredLogEvent) -> serde_json::Value {
+    json!({
+        "timestamp": event.timestamp,
+        "ingestionTime": event.ingestion_time,
+        "logStreamName": event.log_stream_name,
+        "eventId": event.event_id,
+        "message": event.message,
+    })
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// the timestamp format is used for the plain output. the others keep epoch millis
+fn format_event(event: &FilteredLogEvent, format: OutputFormat, timestamp_format: &TimestampFormat, now: i64) -> String {
+    match format {
+        OutputFormat::Plain => {
+            let timestamp = match event.timestamp {
+                Some(timestamp) => timestamp_format.format(timestamp, now),
+                None => String::from(""),
+            };
+            format!(
+                "{} [{}] {}",
+                timestamp,
+                event.log_stream_name.as_deref().unwrap_or(""),
+                event.message.as_deref().unwrap_or("").trim_end(),
+            )
+        },
+        OutputFormat::Json => serde_json::to_string_pretty(&event_to_json(event)).unwrap_or_default(),
+        OutputFormat::Ndjson => event_to_json(event).to_string(),
+        OutputFormat::Csv => [
+            event.timestamp.map(|t| t.to_string()).unwrap_or_default(),
+            event.ingestion_time.map(|t| t.to_string()).unwrap_or_default(),
+            escape_csv(event.log_stream_name.as_deref().unwrap_or("")),
+            escape_csv(event.event_id.as_deref().unwrap_or("")),
+            escape_csv(event.message.as_deref().unwrap_or("")),
+        ].join(","),
+    }
+}
+
+/// writes log events in the output format.
+/// every method returns false if the reader has gone (e.g. `| head`)
+pub struct EventWriter<W: Write> {
+    out: W,
+    format: OutputFormat,
+    timestamp_format: TimestampFormat,
+    count: usize,
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(out: W, format: OutputFormat, timestamp_format: TimestampFormat) -> Self {
+        Self {
+            out,
+            format,
+            timestamp_format,
+            count: 0,
+        }
+    }
+
+    pub fn write_header(&mut self) -> Result<bool> {
+        match self.format {
+            OutputFormat::Csv => self.write(&format!("{}\n", CSV_HEADER)),
+            OutputFormat::Json => self.write("["),
+            _ => Ok(true),
+        }
+    }
+
+    pub fn write_event(&mut self, event: &FilteredLogEvent) -> Result<bool> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+        let line = format_event(event, self.format, &self.timestamp_format, now);
+        let result = match self.format {
+            OutputFormat::Json if self.count == 0 => self.write(&format!("\n{}", line)),
+            OutputFormat::Json => self.write(&format!(",\n{}", line)),
+            _ => self.write(&format!("{}\n", line)),
+        };
+        self.count += 1;
+        result
+    }
+
+    pub fn finish(&mut self) -> Result<bool> {
+        match self.format {
+            OutputFormat::Json if self.count == 0 => self.write("]\n"),
+            OutputFormat::Json => self.write("\n]\n"),
+            _ => Ok(true),
+        }
+    }
+
+    fn write(&mut self, s: &str) -> Result<bool> {
+        write_str(&mut self.out, s)
+    }
+}
+
+/// write `s` and flush. returns false if the reader has gone
+pub fn write_str<W: Write>(out: &mut W, s: &str) -> Result<bool> {
+    match out.write_all(s.as_bytes()).and_then(|_| out.flush()) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// answer MFA token code requests from stdin because there is no TUI
+pub fn spawn_mfa_prompt_reader(prompt: MfaPrompt) {
+    thread::spawn(move || {
+        loop {
+            if let Some(serial) = prompt.get_pending_serial() {
+                eprint!("MFA token code for {}: ", serial);
+                let mut line = String::new();
+                let code = match io::stdin().lock().read_line(&mut line) {
+                    Ok(n) if n > 0 => Some(line.trim().to_string()),
+                    _ => None,
+                };
+                prompt.answer(code);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_event() -> FilteredLogEvent {
+        FilteredLogEvent {
+            event_id: Some(String::from("1")),
+            ingestion_time: Some(1577836801000),
+            log_stream_name: Some(String::from("stream")),
+            // 2020-01-01 00:00:00 UTC
+            timestamp: Some(1577836800000),
+            message: Some(String::from("hello \"world\"\n")),
+        }
+    }
+
+    fn write_events(format: OutputFormat, events: &[FilteredLogEvent]) -> String {
+        let mut buf = vec![];
+        let mut writer = EventWriter::new(&mut buf, format, TimestampFormat::default());
+        writer.write_header().unwrap();
+        for event in events {
+            writer.write_event(event).unwrap();
+        }
+        writer.finish().unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn can_write_plain() {
+        assert_eq!(
+            "2020-01-01 00:00:00 UTC [stream] hello \"world\"\n",
+            write_events(OutputFormat::Plain, &[make_event()]),
+        );
+    }
+
+    #[test]
+    fn plain_output_uses_timestamp_format() {
+        let mut buf = vec![];
+        let timestamp_format = TimestampFormat {
+            format: String::from("%H:%M:%S"),
+            millis: true,
+            ..Default::default()
+        };
+        let mut writer = EventWriter::new(&mut buf, OutputFormat::Plain, timestamp_format);
+        writer.write_event(&make_event()).unwrap();
+        assert_eq!("00:00:00.000 [stream] hello \"world\"\n", String::from_utf8(buf).unwrap());
+    }
+
+    #[test]
+    fn can_write_ndjson() {
+        let output = write_events(OutputFormat::Ndjson, &[make_event(), make_event()]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(2, lines.len());
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(1577836800000i64, value["timestamp"]);
+        assert_eq!(1577836801000i64, value["ingestionTime"]);
+        assert_eq!("stream", value["logStreamName"]);
+        assert_eq!("1", value["eventId"]);
+        assert_eq!("hello \"world\"\n", value["message"]);
+    }
+
+    #[test]
+    fn can_write_json_array() {
+        let output = write_events(OutputFormat::Json, &[make_event(), make_event()]);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(2, value.as_array().unwrap().len());
+        let output = write_events(OutputFormat::Json, &[]);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert!(value.as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_write_csv() {
+        assert_eq!(
+            "timestamp,ingestion_time,log_stream_name,event_id,message\n1577836800000,1577836801000,stream,1,\"hello \"\"world\"\"\n\"\n",
+            write_events(OutputFormat::Csv, &[make_event()]),
+        );
+    }
+
+    #[test]
+    fn can_parse_output_format() {
+        assert_eq!(OutputFormat::Ndjson, "ndjson".parse::<OutputFormat>().unwrap());
+        assert_eq!(OutputFormat::Csv, "csv".parse::<OutputFormat>().unwrap());
+        assert!("xml".parse::<OutputFormat>().is_err());
+        assert_eq!(OutputFormat::Ndjson, OutputFormat::Json.for_stream());
+        assert_eq!(OutputFormat::Csv, OutputFormat::Csv.for_stream());
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use std::io::Write;
+use super::{OutputFormat, EventWriter};
+use crate::handler::build_filter_request;
+use crate::logsource::LogSource;
+use crate::utils::timestamp_format::TimestampFormat;
+
+/// max limit of FilterLogEvents
+const MAX_PAGE_SIZE: usize = 10000;
+
+pub struct SearchOptions {
+    pub log_group_name: String,
+    pub filter_pattern: Option<String>,
+    /// epoch millis (0 means unbounded)
+    pub start: i64,
+    /// epoch millis (0 means unbounded)
+    pub end: i64,
+    pub max_events: Option<usize>,
+    pub format: OutputFormat,
+    pub timestamp_format: TimestampFormat,
+}
+
+/// writes all events matching the options and returns the number of written events
+pub async fn run<S: LogSource, W: Write>(source: S, options: SearchOptions, out: &mut W) -> Result<usize> {
+    let mut writer = EventWriter::new(out, options.format, options.timestamp_format.clone());
+    if !writer.write_header()? {
+        return Ok(0);
+    }
+    let mut count = 0;
+    let mut next_token = None;
+    loop {
+        let limit = match options.max_events {
+            Some(max_events) => (max_events - count).min(MAX_PAGE_SIZE),
+            None => MAX_PAGE_SIZE,
+        };
+        if limit == 0 {
+            break;
+        }
+        let request = build_filter_request(
+            options.log_group_name.clone(),
+            options.filter_pattern.clone().unwrap_or_default(),
+            None,
+            options.start,
+            options.end,
+            next_token,
+            limit as i64,
+        );
+        let response = source.filter_log_events(request).await?;
+        for event in response.events.unwrap_or_default().iter().take(limit) {
+            if !writer.write_event(event)? {
+                return Ok(count);
+            }
+            count += 1;
+        }
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    writer.finish()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logsource::memory::InMemoryLogSource;
+
+    fn make_source() -> InMemoryLogSource {
+        let source = InMemoryLogSource::new(2);
+        source.add_log_group("group");
+        for i in 0..5 {
+            source.add_log_event("group", "stream", 1000 + i, &format!("message {}", i));
+        }
+        source
+    }
+
+    fn make_options(max_events: Option<usize>, format: OutputFormat) -> SearchOptions {
+        SearchOptions {
+            log_group_name: String::from("group"),
+            filter_pattern: None,
+            start: 0,
+            end: 0,
+            max_events,
+            format,
+            timestamp_format: TimestampFormat::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_all_pages() {
+        let mut buf = vec![];
+        let count = run(make_source(), make_options(None, OutputFormat::Ndjson), &mut buf).await.unwrap();
+        assert_eq!(5, count);
+        let output = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(5, lines.len());
+        assert!(lines[4].contains("message 4"));
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_events() {
+        let source = make_source();
+        let mut buf = vec![];
+        let count = run(source.clone(), make_options(Some(3), OutputFormat::Csv), &mut buf).await.unwrap();
+        assert_eq!(3, count);
+        let output = String::from_utf8(buf).unwrap();
+        assert_eq!(4, output.lines().count());
+        assert_eq!(2, source.get_filter_requests().len());
+    }
+
+    #[tokio::test]
+    async fn passes_filter_and_time_range() {
+        let source = make_source();
+        let options = SearchOptions {
+            filter_pattern: Some(String::from("message")),
+            start: 1001,
+            end: 1003,
+            ..make_options(None, OutputFormat::Plain)
+        };
+        let mut buf = vec![];
+        assert_eq!(3, run(source.clone(), options, &mut buf).await.unwrap());
+        let request = &source.get_filter_requests()[0];
+        assert_eq!(Some(1001), request.start_time);
+        assert_eq!(Some(1003), request.end_time);
+        assert_eq!(Some(String::from("message")), request.filter_pattern);
+    }
+}
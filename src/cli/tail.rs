@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::io::Write;
+use std::time::Duration;
+use super::{OutputFormat, EventWriter};
+use crate::handler::now_millis;
+use crate::logsource::LogSource;
+use crate::tail::{TailEngine, TailTarget};
+use crate::utils::timestamp_format::TimestampFormat;
+
+pub struct TailOptions {
+    pub log_group_name: String,
+    pub filter_pattern: Option<String>,
+    /// how far the first poll looks back
+    pub since: Duration,
+    pub interval: Duration,
+    /// how far each poll looks back before the cursor
+    pub look_back: Duration,
+    pub format: OutputFormat,
+    pub timestamp_format: TimestampFormat,
+}
+
+/// print new events until interrupted or the output is closed
+pub async fn run<S: LogSource, W: Write>(source: S, options: TailOptions, out: &mut W) -> Result<()> {
+    let target = TailTarget {
+        log_group_name: options.log_group_name.clone(),
+        filter_pattern: options.filter_pattern.clone(),
+        log_stream_name_prefix: None,
+    };
+    let mut engine = TailEngine::new(source, target, options.since);
+    engine.set_look_back(options.look_back);
+    let mut writer = EventWriter::new(out, options.format.for_stream(), options.timestamp_format.clone());
+    let interval = options.interval;
+    let tail = async {
+        if !writer.write_header()? {
+            return Ok(());
+        }
+        loop {
+            for event in engine.poll(now_millis()).await? {
+                if !writer.write_event(&event)? {
+                    return Ok(());
+                }
+            }
+            tokio::time::delay_for(interval).await;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => Ok(()),
+        result = tail => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logsource::memory::InMemoryLogSource;
+
+    #[tokio::test]
+    async fn stops_when_output_is_closed() {
+        struct ClosedPipe;
+        impl Write for ClosedPipe {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let source = InMemoryLogSource::new(10);
+        source.add_log_group("group");
+        source.add_log_event("group", "stream", now_millis() - 1_000, "message");
+        let options = TailOptions {
+            log_group_name: String::from("group"),
+            filter_pattern: None,
+            since: Duration::from_secs(60),
+            interval: Duration::from_millis(10),
+            look_back: Duration::from_secs(60),
+            format: OutputFormat::Plain,
+            timestamp_format: TimestampFormat::default(),
+        };
+        assert!(run(source, options, &mut ClosedPipe).await.is_ok());
+    }
+}
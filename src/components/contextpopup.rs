@@ -0,0 +1,141 @@
+use crate::components::Drawable;
+use crate::utils::{self, StatefulTable, logevent_list::LogEventList, timestamp_format::TimestampFormat};
+use tui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Rect},
+    widgets::{Block, Borders, Clear, Row, Table, TableState},
+    style::{Style, Color},
+    Frame,
+};
+use crossterm::event::{KeyEvent, KeyCode};
+use std::io::Stdout;
+use async_trait::async_trait;
+
+/// popup which shows the events around an event in its log stream
+pub struct ContextPopup {
+    log_stream_name: String,
+    events: LogEventList,
+    /// index of the event which the context is opened for
+    highlighted: Option<usize>,
+    fetching: bool,
+    error: Option<String>,
+    timestamp_format: TimestampFormat,
+}
+
+impl ContextPopup {
+    pub fn new(log_stream_name: &str, timestamp_format: TimestampFormat) -> Self {
+        Self {
+            log_stream_name: log_stream_name.to_string(),
+            events: LogEventList::new(vec![]),
+            highlighted: None,
+            fetching: true,
+            error: None,
+            timestamp_format,
+        }
+    }
+
+    /// update the events. the highlighted event is selected when the events change
+    pub fn set_events(&mut self, events: &LogEventList, highlighted: Option<usize>, fetching: bool) {
+        self.fetching = fetching;
+        if self.events.is_same(events) && self.highlighted == highlighted {
+            return;
+        }
+        let mut state = TableState::default();
+        state.select(highlighted);
+        self.events = events.clone_with_state(Some(state));
+        self.events.set_timestamp_format(self.timestamp_format.clone());
+        self.highlighted = highlighted;
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    fn get_title(&self) -> String {
+        if self.fetching {
+            format!("Context: {} [Fetching ...]", self.log_stream_name)
+        } else if let Some(error) = &self.error {
+            format!("Context: {} [Error: {}] (Esc: close)", self.log_stream_name, error)
+        } else {
+            format!("Context: {} (Esc: close)", self.log_stream_name)
+        }
+    }
+}
+
+#[async_trait]
+impl Drawable for ContextPopup {
+    fn draw(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        let area = utils::centered_rect(80, 70, area);
+        let labels = self.events.get_labels();
+        let highlighted = self.highlighted;
+        let rows = labels.iter().enumerate().map(|(i, label)| {
+            if Some(i) == highlighted {
+                Row::StyledData(label.iter(), Style::default().fg(Color::Yellow))
+            } else {
+                Row::Data(label.iter())
+            }
+        });
+        let title = self.get_title();
+        let table = Table::new(["Timestamp", "Message"].iter(), rows)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(title.as_str())
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+            )
+            .widths(&[
+                Constraint::Percentage(15),
+                Constraint::Percentage(100),
+            ]);
+        f.render_widget(Clear, area);
+        if let Some(ref mut state) = self.events.get_state() {
+            f.render_stateful_widget(table, area, state);
+        }
+    }
+
+    async fn handle_event(&mut self, event: KeyEvent) -> bool {
+        let mut solved = true;
+        match event.code {
+            KeyCode::Down => {
+                self.events.next();
+            },
+            KeyCode::Up => self.events.previous(),
+            _ => solved = false
+        }
+        solved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_logs::FilteredLogEvent;
+
+    fn make_events(len: usize) -> LogEventList {
+        LogEventList::new(
+            (0..len)
+                .map(|i| FilteredLogEvent {
+                    event_id: Some(format!("context#{}", i)),
+                    timestamp: Some(i as i64),
+                    ..Default::default()
+                })
+                .collect()
+        )
+    }
+
+    #[test]
+    fn highlighted_event_is_selected() {
+        let mut popup = ContextPopup::new("stream", TimestampFormat::default());
+        assert_eq!("Context: stream [Fetching ...]", popup.get_title());
+        popup.set_events(&make_events(5), Some(2), false);
+        assert_eq!(Some(2), popup.events.get_state().unwrap().selected());
+        assert_eq!("Context: stream (Esc: close)", popup.get_title());
+        popup.events.next();
+        popup.set_events(&make_events(5), Some(2), false);
+        assert_eq!(Some(3), popup.events.get_state().unwrap().selected());
+    }
+}
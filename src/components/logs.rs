@@ -1,12 +1,17 @@
 use crate::components::{
     Drawable,
+    contextpopup::ContextPopup,
+    rangepopup::RangePopup,
     textinput::TextInputComponent,
 };
 use crate::utils::logevent_list::LogEventList;
+use crate::utils::queryresult_table::QueryResultTable;
 use crate::utils::StatefulTable;
+use crate::utils::timestamp_format::TimestampFormat;
 use crate::utils;
 use crate::globalstate::{GlobalState, GlobalStateTail};
 use crate::instruction::Instruction;
+use crate::handler::is_query_running;
 use tui::{
     backend::CrosstermBackend,
     layout::{
@@ -28,13 +33,14 @@ use tui::{
     Frame,
 };
 use crossterm::event::{KeyEvent, KeyCode, KeyModifiers};
+use std::collections::HashSet;
 use std::io::Stdout;
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
+use std::time::{SystemTime, UNIX_EPOCH, Duration, Instant};
 use async_trait::async_trait;
 use std::sync::{Arc, Mutex, mpsc::Sender};
 
-#[derive(Debug)]
-enum SearchMode {
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchMode {
     Tail,
     All,
     OneM,
@@ -44,8 +50,84 @@ enum SearchMode {
     Range(i64, i64),
 }
 
+impl SearchMode {
+    /// the presets of relative ranges ending now
+    pub const PRESETS: [SearchMode; 4] = [
+        SearchMode::OneM,
+        SearchMode::ThirtyM,
+        SearchMode::OneH,
+        SearchMode::TwelveH,
+    ];
+
+    /// duration until now. None if the range isn't relative
+    pub fn get_duration(&self) -> Option<Duration> {
+        match self {
+            SearchMode::Tail | SearchMode::OneM => Some(Duration::from_secs(60)),
+            SearchMode::ThirtyM => Some(Duration::from_secs(900)),
+            SearchMode::OneH => Some(Duration::from_secs(3600)),
+            SearchMode::TwelveH => Some(Duration::from_secs(43200)),
+            SearchMode::All | SearchMode::Range(_, _) => None,
+        }
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            SearchMode::Tail => "tail",
+            SearchMode::All => "all",
+            SearchMode::OneM => "1m",
+            SearchMode::ThirtyM => "15m",
+            SearchMode::OneH => "1h",
+            SearchMode::TwelveH => "12h",
+            SearchMode::Range(_, _) => "range",
+        }
+    }
+
+    /// start and end in epoch millis. (0, 0) means unbounded
+    pub fn get_range(&self) -> (i64, i64) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        match self {
+            SearchMode::All => (0, 0),
+            SearchMode::Range(start, end) => (*start, *end),
+            _ => {
+                let duration = self.get_duration().unwrap_or_default();
+                let start = now
+                    .checked_sub(duration)
+                    .unwrap_or(Duration::from_secs(0))
+                    .as_millis();
+                (start as i64, now.as_millis() as i64)
+            },
+        }
+    }
+}
+
+/// how the text of the search area is used
+#[derive(Debug, PartialEq)]
+enum QueryMode {
+    /// filter pattern of FilterLogEvents
+    Filter,
+    /// CloudWatch Logs Insights query
+    Insights,
+}
+
+/// interval of GetQueryResults while an Insights query is running
+const QUERY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// colors of the log groups tailed together, in the order of the log groups
+const LOG_GROUP_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Blue,
+    Color::LightRed,
+    Color::LightYellow,
+];
+
 pub struct Logs {
     search_area: TextInputComponent,
+    /// prefix of the log stream names which the events are searched in
+    stream_prefix_area: TextInputComponent,
+    is_stream_prefix_active: bool,
+    show_log_stream_name: bool,
     title: String,
     event_list: LogEventList,
     tailed_event_list: LogEventList,
@@ -58,12 +140,32 @@ pub struct Logs {
     cached_tailed_labels: Vec<Vec<String>>,
     tx: Sender<Instruction>,
     search_mode: SearchMode,
+    query_mode: QueryMode,
+    query_results: QueryResultTable,
+    last_query_poll: Option<Instant>,
+    /// log groups selected in the menu for Insights queries
+    query_log_group_names: Vec<String>,
+    range_popup: Option<RangePopup>,
+    context_popup: Option<ContextPopup>,
+    /// the tail keeps the selection instead of following the latest event
+    tail_paused: bool,
+    /// events received while the tail is paused
+    tail_new_events: usize,
+    timestamp_format: TimestampFormat,
+    /// log stream whose events are paged with GetLogEvents instead of searching the log group
+    log_stream_name: Option<String>,
+    /// filter pattern and stream prefix applied with Enter. the tail follows them instead of the text being edited
+    tail_filter_pattern: String,
+    tail_stream_prefix: Option<String>,
 }
 
 impl Logs {
     pub fn new(title: &str, tx: Sender<Instruction>, state: Arc<Mutex<GlobalState>>, tail_state: Arc<Mutex<GlobalStateTail>>) -> Self {
         Self {
             search_area: TextInputComponent::new("Filter(f)", ""),
+            stream_prefix_area: TextInputComponent::new("Stream prefix(s)", ""),
+            is_stream_prefix_active: false,
+            show_log_stream_name: false,
             title: title.to_string(),
             event_list: LogEventList::new(vec![]),
             tailed_event_list: LogEventList::new(vec![]),
@@ -76,6 +178,18 @@ impl Logs {
             cached_tailed_labels: vec![vec![]],
             tx,
             search_mode: SearchMode::All,
+            query_mode: QueryMode::Filter,
+            query_results: QueryResultTable::new(),
+            last_query_poll: None,
+            query_log_group_names: vec![],
+            range_popup: None,
+            context_popup: None,
+            tail_paused: false,
+            tail_new_events: 0,
+            timestamp_format: TimestampFormat::default(),
+            log_stream_name: None,
+            tail_filter_pattern: String::new(),
+            tail_stream_prefix: None,
         }
     }
 
@@ -90,19 +204,223 @@ impl Logs {
 
     pub fn activate_search_area(&mut self) {
         self.is_search_active = true;
+        self.is_stream_prefix_active = false;
         self.search_area.select();
+        self.stream_prefix_area.deselect();
+    }
+
+    fn activate_stream_prefix_area(&mut self) {
+        self.is_search_active = true;
+        self.is_stream_prefix_active = true;
+        self.stream_prefix_area.select();
+        self.search_area.deselect();
     }
 
     pub fn activate_logs_area(&mut self) {
         self.is_search_active = false;
+        self.is_stream_prefix_active = false;
         self.search_area.set_input_mode(crate::components::textinput::InputMode::NormalMode);
         self.search_area.deselect();
+        self.stream_prefix_area.set_input_mode(crate::components::textinput::InputMode::NormalMode);
+        self.stream_prefix_area.deselect();
+    }
+
+    fn get_stream_prefix(&self) -> Option<String> {
+        let prefix = self.stream_prefix_area.get_text();
+        if prefix.is_empty() {
+            None
+        } else {
+            Some(prefix.to_string())
+        }
+    }
+
+    /// apply the texts of the search areas to the tail
+    fn commit_search(&mut self) {
+        self.tail_filter_pattern = self.search_area.get_text().to_string();
+        self.tail_stream_prefix = self.get_stream_prefix();
+    }
+
+    /// show or hide the log stream name column
+    fn toggle_log_stream_name(&mut self) {
+        self.show_log_stream_name = !self.show_log_stream_name;
+        self.event_list.set_show_log_stream_name(self.show_log_stream_name);
+        self.tailed_event_list.set_show_log_stream_name(self.show_log_stream_name);
+        self.cached_labels = self.event_list.get_labels();
+        self.cached_tailed_labels = self.tailed_event_list.get_labels();
     }
 
     pub fn is_active(&self) -> bool {
         self.is_active
     }
 
+    pub fn set_timestamp_format(&mut self, timestamp_format: TimestampFormat) {
+        self.timestamp_format = timestamp_format;
+        self.event_list.set_timestamp_format(self.timestamp_format.clone());
+        self.tailed_event_list.set_timestamp_format(self.timestamp_format.clone());
+        self.cached_labels = self.event_list.get_labels();
+        self.cached_tailed_labels = self.tailed_event_list.get_labels();
+    }
+
+    /// switch between the absolute and the relative timestamps
+    fn toggle_relative_timestamp(&mut self) {
+        let mut timestamp_format = self.timestamp_format.clone();
+        timestamp_format.relative = !timestamp_format.relative;
+        self.set_timestamp_format(timestamp_format);
+    }
+
+    /// "timestamp\nmessage" of the event for the full text area
+    fn get_full_text(list: &LogEventList, idx: usize, width: usize) -> Option<String> {
+        let message = list.get_log_event_text(idx)?;
+        let message = utils::insert_new_line_at(width, message.as_str());
+        match list.get_timestamp_label(idx) {
+            Some(timestamp) => Some(format!("{}\n{}", timestamp, message)),
+            None => Some(message),
+        }
+    }
+
+    /// open the popup of the events around the selected event in its log stream
+    fn open_context(&mut self) {
+        let current_log_group_name = &self.log_group_name;
+        let list = if self.is_tail_mode() {
+            &mut self.tailed_event_list
+        } else {
+            &mut self.event_list
+        };
+        let idx = match list.get_state().and_then(|s| s.selected()) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let (log_stream_name, timestamp, message) = match list.get_event(idx) {
+            Some(event) => match (&event.log_stream_name, event.timestamp) {
+                (Some(log_stream_name), Some(timestamp)) => (
+                    log_stream_name.clone(),
+                    timestamp,
+                    event.message.clone().unwrap_or_default(),
+                ),
+                _ => return,
+            },
+            None => return,
+        };
+        // the events of the merged tail have their own log groups
+        let log_group_name = match list.get_log_group_name(idx).or(current_log_group_name.as_ref()) {
+            Some(log_group_name) => log_group_name.clone(),
+            None => return,
+        };
+        {
+            let mut state = self.state.lock().unwrap();
+            state.context_events.clear_items();
+            state.context_highlighted = None;
+            state.context_fetching = true;
+        }
+        self.context_popup = Some(ContextPopup::new(&log_stream_name, self.timestamp_format.clone()));
+        self.tx.send(Instruction::FetchContext(log_group_name, log_stream_name, timestamp, message)).unwrap();
+    }
+
+    fn pause_tail(&mut self) {
+        self.tail_paused = true;
+    }
+
+    /// take the events polled by the tail. the selected event is kept while paused,
+    /// and the latest event is followed otherwise
+    fn update_tailed_events(&mut self, events: &LogEventList) {
+        let prev_len = self.tailed_event_list.len();
+        let prev_event_ids: HashSet<String> = (0..prev_len)
+            .filter_map(|idx| self.tailed_event_list.get_event(idx)?.event_id.clone())
+            .collect();
+        // the late events are inserted before the selected event
+        let selected_event_id = self.tailed_event_list
+            .get_state()
+            .and_then(|s| s.selected())
+            .and_then(|idx| self.tailed_event_list.get_event(idx))
+            .and_then(|e| e.event_id.clone());
+        self.tailed_event_list = events.clone_with_state(self.tailed_event_list.get_state());
+        self.tailed_event_list.set_timestamp_format(self.timestamp_format.clone());
+        self.tailed_event_list.set_show_log_stream_name(self.show_log_stream_name);
+        self.cached_tailed_labels = self.tailed_event_list.get_labels();
+        // the tail is started over if the events decrease. the oldest ones are dropped only to keep the length
+        if self.tail_paused && self.tailed_event_list.len() >= prev_len {
+            let new_events = (0..self.tailed_event_list.len())
+                .filter_map(|idx| self.tailed_event_list.get_event(idx)?.event_id.as_ref())
+                .filter(|event_id| !prev_event_ids.contains(*event_id))
+                .count();
+            self.tail_new_events += new_events;
+            if let Some(idx) = selected_event_id.and_then(|id| self.tailed_event_list.position(&id)) {
+                let mut new_state = TableState::default();
+                new_state.select(Some(idx));
+                self.tailed_event_list.set_state(new_state);
+            }
+        } else {
+            self.resume_tail();
+        }
+    }
+
+    /// follow the latest event again
+    fn resume_tail(&mut self) {
+        self.tail_paused = false;
+        self.tail_new_events = 0;
+        let mut state = TableState::default();
+        state.select(Some(self.tailed_event_list.len().saturating_sub(1)));
+        self.tailed_event_list.set_state(state);
+    }
+
+    /// headers and widths of the events table
+    fn get_columns(&self, show_log_group_name: bool) -> (Vec<&'static str>, Vec<Constraint>) {
+        let mut headers = vec!["Timestamp"];
+        let mut widths = vec![Constraint::Percentage(15)];
+        if show_log_group_name {
+            headers.push("Group");
+            widths.push(Constraint::Percentage(15));
+        }
+        if self.show_log_stream_name {
+            headers.push("Stream");
+            widths.push(Constraint::Percentage(15));
+        }
+        headers.push("Message");
+        widths.push(Constraint::Percentage(100));
+        (headers, widths)
+    }
+
+    /// rows of the tail are colored by their log groups when several log groups are tailed
+    fn get_tail_row_styles(&self) -> Vec<Style> {
+        let log_group_names = self.get_query_log_group_names();
+        (0..self.cached_tailed_labels.len())
+            .map(|idx| {
+                self.tailed_event_list
+                    .get_log_group_name(idx)
+                    .and_then(|name| log_group_names.iter().position(|n| n == name))
+                    .map(|pos| Style::default().fg(LOG_GROUP_COLORS[pos % LOG_GROUP_COLORS.len()]))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    fn get_tail_title(&self) -> String {
+        let base = match self.get_query_log_group_names().len() {
+            0 | 1 => format!("{} - Tail", self.title),
+            n => format!("{} - Tail({} groups)", self.title, n),
+        };
+        let base = match self.tail_state.try_lock() {
+            Ok(state) => {
+                let base = format!(
+                    "{} [every {}, look-back {}]",
+                    base,
+                    utils::format_duration(state.settings.interval),
+                    utils::format_duration(state.settings.look_back),
+                );
+                match &state.log_events_error {
+                    Some(error) => format!("{} [Error: {}]", base, error),
+                    None => base,
+                }
+            },
+            Err(_) => base,
+        };
+        if self.tail_paused {
+            format!("{} [paused: {} new events] (p: follow)", base, self.tail_new_events)
+        } else {
+            format!("{} (p: pause, +/-: interval, </>: look-back)", base)
+        }
+    }
+
     pub fn set_log_group_name(&mut self, log_group_name: Option<String>) {
         self.log_group_name = log_group_name;
     }
@@ -111,67 +429,81 @@ impl Logs {
         self.log_group_name.clone()
     }
 
+    pub fn set_log_stream_name(&mut self, log_stream_name: Option<String>) {
+        self.log_stream_name = log_stream_name;
+    }
+
+    /// send the request of the older (backward) or the newer (forward) page of the log stream
+    fn fetch_stream_page(&self, forward: bool) {
+        let (log_group_name, log_stream_name) = match (&self.log_group_name, &self.log_stream_name) {
+            (Some(group), Some(stream)) => (group.clone(), stream.clone()),
+            _ => return,
+        };
+        let token = match self.state.try_lock() {
+            Ok(state) if !state.stream_events_fetching => {
+                if forward {
+                    state.stream_events_forward_token.clone()
+                } else {
+                    state.stream_events_backward_token.clone()
+                }
+            },
+            _ => return,
+        };
+        if token.is_some() {
+            let (start, end) = self.get_search_range();
+            self.tx.send(Instruction::FetchStreamEvents(log_group_name, log_stream_name, token, start, end)).unwrap();
+        }
+    }
+
+    pub fn set_query_log_group_names(&mut self, log_group_names: Vec<String>) {
+        self.query_log_group_names = log_group_names;
+    }
+
+    /// the selected log groups, or the current log group if nothing is selected
+    fn get_query_log_group_names(&self) -> Vec<String> {
+        if self.query_log_group_names.is_empty() {
+            self.log_group_name.iter().cloned().collect()
+        } else {
+            self.query_log_group_names.clone()
+        }
+    }
+
     fn clear_cache(&mut self) {
         self.cached_labels = vec![];
         self.cached_tailed_labels = vec![];
     }
 
     fn get_search_range(&self) -> (i64, i64) {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        match self.search_mode {
-            SearchMode::Tail => {
-                let start = now
-                    .checked_sub(Duration::from_secs(60))
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_millis();
-                (start as i64, now.as_millis() as i64)
-            },
-            SearchMode::OneM => {
-                let start = now
-                    .checked_sub(Duration::from_secs(60))
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_millis();
-                (start as i64, now.as_millis() as i64)
-            },
-            SearchMode::ThirtyM => {
-                let start = now
-                    .checked_sub(Duration::from_secs(900))
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_millis();
-                (start as i64, now.as_millis() as i64)
-            },
-            SearchMode::OneH => {
-                let start = now
-                    .checked_sub(Duration::from_secs(3600))
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_millis();
-                (start as i64, now.as_millis() as i64)
-            },
-            SearchMode::TwelveH => {
-                let start = now
-                    .checked_sub(Duration::from_secs(43200))
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_millis();
-                (start as i64, now.as_millis() as i64)
-            },
-            SearchMode::All => {
-                (0, 0)
-            },
-            SearchMode::Range(start, end) => {
-                (start, end)
-            },
-        }
+        self.search_mode.get_range()
     }
 
     pub fn clear_results(&mut self) {
         self.event_list.clear_items();
         self.tailed_event_list.clear_items();
+        self.query_results.clear_items();
         self.clear_cache();
-        self.state.lock().unwrap().reset_log_event_results();
+        self.stop_query();
+        self.tail_paused = false;
+        self.tail_new_events = 0;
+        let mut state = self.state.lock().unwrap();
+        state.reset_log_event_results();
+        state.reset_stream_events();
+        state.reset_query_results();
         self.tail_state.lock().unwrap().reset_log_event_results();
     }
 
     pub fn fetch_log_events(&self) {
+        if self.is_insights_mode() {
+            self.start_query();
+            return;
+        }
+        if self.is_stream_mode() {
+            if let (Some(group), Some(stream)) = (&self.log_group_name, &self.log_stream_name) {
+                let (start, end) = self.get_search_range();
+                self.tx.send(Instruction::FetchStreamEvents(group.clone(), stream.clone(), None, start, end)).unwrap();
+            }
+            return;
+        }
         match self.state.try_lock() {
             Ok(m_guard) => {
                 if !m_guard.log_events_fetching {
@@ -180,6 +512,7 @@ impl Logs {
                         self.tx.send(Instruction::FetchLogEvents(
                             log_group_name.clone(),
                             self.search_area.get_text().to_string(),
+                            self.get_stream_prefix(),
                             start.clone(),
                             end.clone(),
                         )).unwrap();
@@ -190,14 +523,86 @@ impl Logs {
         }
     }
 
+    fn start_query(&self) {
+        let query = self.search_area.get_text();
+        if query.is_empty() {
+            return;
+        }
+        let log_group_names = self.get_query_log_group_names();
+        if !log_group_names.is_empty() {
+            let (start, end): (i64, i64) = self.get_search_range();
+            self.tx.send(Instruction::StartQuery(
+                log_group_names,
+                query.to_string(),
+                start,
+                end,
+            )).unwrap();
+        }
+    }
+
+    /// send StopQuery if the Insights query is running
+    fn stop_query(&self) {
+        let state = self.state.lock().unwrap();
+        if let Some(query_id) = &state.query_id {
+            if is_query_running(&state.query_status) {
+                self.tx.send(Instruction::StopQuery(query_id.clone())).unwrap();
+            }
+        }
+    }
+
+    /// send GetQueryResults periodically until the query finishes
+    fn poll_query_results(&mut self) {
+        if let Some(last_poll) = self.last_query_poll {
+            if last_poll.elapsed() < QUERY_POLL_INTERVAL {
+                return;
+            }
+        }
+        if let Ok(state) = self.state.try_lock() {
+            if let Some(query_id) = &state.query_id {
+                if is_query_running(&state.query_status) && !state.query_fetching {
+                    self.tx.send(Instruction::FetchQueryResults(query_id.clone())).unwrap();
+                    self.last_query_poll = Some(Instant::now());
+                }
+            }
+        }
+    }
+
+    fn get_query_title(&self) -> String {
+        let base = match self.get_query_log_group_names().len() {
+            0 | 1 => format!("{} - Insights", self.title),
+            n => format!("{} - Insights({} groups)", self.title, n),
+        };
+        match self.state.try_lock() {
+            Ok(state) => {
+                let status = state.query_status.as_deref().unwrap_or("Ready");
+                match &state.query_error {
+                    Some(error) => format!("{}: {} ({})", base, status, error),
+                    None if is_query_running(&state.query_status) => {
+                        format!("{}: {} (c: cancel)", base, status)
+                    },
+                    None => format!("{}: {}", base, status),
+                }
+            },
+            Err(_) => base,
+        }
+    }
+
     fn get_search_area_title(&self) -> String {
-        let base = "Filter(f) - Mode: ";
+        let base = if self.is_insights_mode() {
+            "Query(f) - Mode: "
+        } else if self.is_stream_mode() {
+            // GetLogEvents can't filter
+            "Filter(f, not applied to the stream) - Mode: "
+        } else {
+            "Filter(f) - Mode: "
+        };
         let mut tail = "[ ]tail";
         let mut onem = "[ ]1m";
         let mut thirtym = "[ ]15m";
         let mut oneh = "[ ]1h";
         let mut twelveh = "[ ]12h";
-        let mut range = "[ ]range(2020-12-12-01:01:00~2020-12-15-01:00:30)";
+        let range_label;
+        let mut range = "[ ]range";
         match self.search_mode {
             SearchMode::OneM => {
                 onem = "[x]1m";
@@ -211,15 +616,54 @@ impl Logs {
             SearchMode::TwelveH => {
                 twelveh = "[x]12h";
             },
-            SearchMode::Range(_start, _end) => {
-                range = "[x]range(2020-12-12-01:01:00~2020-12-15-01:00:30)";
+            SearchMode::Range(start, end) => {
+                range_label = format!(
+                    "[x]range({}~{})",
+                    utils::format_local_time(start),
+                    utils::format_local_time(end),
+                );
+                range = range_label.as_str();
             },
             SearchMode::Tail => {
                 tail = "[x]tail";
             },
             SearchMode::All => {},
         }
-        format!("{}{}{}{}{}{}{}", base, tail, onem, thirtym, oneh, twelveh, range)
+        let insights = if self.is_insights_mode() {
+            "[x]insights"
+        } else {
+            "[ ]insights"
+        };
+        format!("{}{}{}{}{}{}{}{}", base, tail, onem, thirtym, oneh, twelveh, range, insights)
+    }
+
+    /// search in the mode. the Insights mode is kept unless it's tail
+    pub fn set_search_mode(&mut self, search_mode: SearchMode) {
+        self.search_mode = search_mode;
+        if self.is_tail_mode() {
+            self.query_mode = QueryMode::Filter;
+        }
+    }
+
+    /// handle the events while the range popup is open
+    async fn handle_range_popup_event(&mut self, event: KeyEvent) {
+        if let Some(popup) = &mut self.range_popup {
+            if !popup.handle_event(event).await {
+                match event.code {
+                    KeyCode::Enter => match popup.get_range() {
+                        Ok((start, end)) => {
+                            self.range_popup = None;
+                            self.set_search_mode(SearchMode::Range(start, end));
+                            self.clear_results();
+                            self.fetch_log_events();
+                        },
+                        Err(e) => popup.set_error(e.to_string()),
+                    },
+                    KeyCode::Esc => self.range_popup = None,
+                    _ => {},
+                }
+            }
+        }
     }
 
     fn clear_search_mode(&mut self) {
@@ -228,10 +672,33 @@ impl Logs {
 
     fn sync_global_state_tail(&self) {
         if let Ok(mut state) = self.tail_state.try_lock() {
-            if let Some(log_group_name) = &self.log_group_name {
-                state.log_events_selected_log_group_name = log_group_name.clone();
-                state.log_events_filter_pattern = Some(self.search_area.get_text().to_string());
-            } 
+            state.active = self.is_tail_mode();
+            if self.log_group_name.is_some() {
+                state.log_events_selected_log_group_names = self.get_query_log_group_names();
+                state.log_events_filter_pattern = Some(self.tail_filter_pattern.clone());
+                state.log_events_stream_prefix = self.tail_stream_prefix.clone();
+            }
+        }
+    }
+
+    fn is_insights_mode(&self) -> bool {
+        self.query_mode == QueryMode::Insights
+    }
+
+    fn is_stream_mode(&self) -> bool {
+        self.log_stream_name.is_some() && !self.is_insights_mode() && !self.is_tail_mode()
+    }
+
+    fn get_events_title(&self) -> String {
+        let title = match &self.log_stream_name {
+            Some(stream) if self.is_stream_mode() => {
+                format!("{} - Stream: {} (PageUp: older, PageDown: newer)", self.title, stream)
+            },
+            _ => self.title.clone(),
+        };
+        match self.state.try_lock().ok().and_then(|state| state.log_events_error.clone()) {
+            Some(error) => format!("{} [Error: {}]", title, error),
+            None => title,
         }
     }
 
@@ -258,45 +725,76 @@ impl Drawable for Logs {
             .split(area);
         let mut log_text = String::from("");
         match self.search_mode {
+            _ if self.is_insights_mode() => {
+                self.poll_query_results();
+                if let Ok(m_guard) = self.state.try_lock() {
+                    if !self.query_results.is_same(&m_guard.query_results) {
+                        self.query_results = m_guard.query_results.clone_with_state(self.query_results.get_state());
+                    }
+                    if let Some(s) = self.query_results.get_state() {
+                        if let Some(idx) = s.selected() {
+                            if let Some(text) = self.query_results.get_row_text(idx) {
+                                log_text = text;
+                            }
+                        }
+                    }
+                }
+            },
+            _ if self.is_stream_mode() => {
+                if let Ok(m_guard) = self.state.try_lock() {
+                    if !self.event_list.is_same(&m_guard.stream_events) {
+                        self.event_list = m_guard.stream_events.clone_with_state(Some(TableState::default()));
+                        self.event_list.set_timestamp_format(self.timestamp_format.clone());
+                        self.event_list.set_show_log_stream_name(self.show_log_stream_name);
+                        self.cached_labels = self.event_list.get_labels();
+                    } else if self.timestamp_format.relative {
+                        self.cached_labels = self.event_list.get_labels();
+                    }
+                    if let Some(s) = self.event_list.get_state() {
+                        if let Some(idx) = s.selected() {
+                            if let Some(text) = Self::get_full_text(&self.event_list, idx, chunks[2].width as usize - 2) {
+                                log_text = text;
+                            }
+                        }
+                    }
+                }
+            },
             SearchMode::Tail => {
-                match &mut self.tail_state.try_lock() {
+                let tail_state = Arc::clone(&self.tail_state);
+                match &mut tail_state.try_lock() {
                     Ok(m_guard) => {
                         if !self.tailed_event_list.is_same(&m_guard.log_events) {
-                            self.tailed_event_list = m_guard.log_events.clone_with_state(self.tailed_event_list.get_state());
+                            self.update_tailed_events(&m_guard.log_events);
+                        } else if self.timestamp_format.relative {
                             self.cached_tailed_labels = self.tailed_event_list.get_labels();
-                            let mut new_state = TableState::default();
-                            new_state.select(Some(self.cached_tailed_labels.len().saturating_sub(1)));
-                            self.tailed_event_list.set_state(new_state);
                         }
                         if let Some(s) = self.tailed_event_list.get_state() {
                             if let Some(idx) = s.selected() {
-                                if let Some(msg) = m_guard.log_events.get_log_event_text(idx) {
-                                    log_text = utils::insert_new_line_at(
-                                        chunks[2].width as usize - 2,
-                                        msg.as_str(),
-                                    );
+                                if let Some(text) = Self::get_full_text(&self.tailed_event_list, idx, chunks[2].width as usize - 2) {
+                                    log_text = text;
                                 }
                             }
                         }
                     },
                     Err(_) => {}
-                }
+                };
             },
             _ => {
                 log_text = match &mut self.state.try_lock() {
                     Ok(m_guard) => {
                         if !self.event_list.is_same(&m_guard.log_events) {
                             self.event_list = m_guard.log_events.clone_with_state(self.event_list.get_state());
+                            self.event_list.set_timestamp_format(self.timestamp_format.clone());
+                            self.event_list.set_show_log_stream_name(self.show_log_stream_name);
+                            self.cached_labels = self.event_list.get_labels();
+                        } else if self.timestamp_format.relative {
                             self.cached_labels = self.event_list.get_labels();
                         }
                         let mut result = String::from("");
                         if let Some(s) = self.event_list.get_state() {
                             if let Some(idx) = s.selected() {
-                                if let Some(msg) = m_guard.log_events.get_log_event_text(idx) {
-                                    result = utils::insert_new_line_at(
-                                        chunks[2].width as usize - 2,
-                                        msg.as_str(),
-                                    );
+                                if let Some(text) = Self::get_full_text(&self.event_list, idx, chunks[2].width as usize - 2) {
+                                    result = text;
                                 }
                             }
                         };
@@ -320,10 +818,17 @@ impl Drawable for Logs {
             Err(_) => {}
         }
 
+        let events_title = self.get_events_title();
+        let (headers, widths) = self.get_columns(false);
+        let (tail_headers, tail_widths) = self.get_columns(self.tailed_event_list.has_log_group_names());
+        let tail_styles = self.get_tail_row_styles();
         let rows = self.cached_labels.iter().map(|i| Row::Data(i.iter()));
-        let tailed_rows = self.cached_tailed_labels.iter().map(|i| Row::Data(i.iter()));
+        let tailed_rows = self.cached_tailed_labels
+            .iter()
+            .zip(tail_styles)
+            .map(|(i, style)| Row::StyledData(i.iter(), style));
         let event_table_block = Table::new(
-            ["Timestamp", "Message"].iter(),
+            headers.iter(),
             rows
         )
             .block(
@@ -336,18 +841,16 @@ impl Drawable for Logs {
                             Style::default().fg(Color::White)
                         }
                     )
-                    .title(self.title.as_str())
+                    .title(events_title.as_str())
             )
             .highlight_style(
                 Style::default()
                     .bg(Color::DarkGray)
             )
-            .widths(&[
-                Constraint::Percentage(15),
-                Constraint::Percentage(100),
-            ]);
+            .widths(&widths);
+        let tail_title = self.get_tail_title();
         let tail_event_table_block = Table::new(
-            ["Timestamp", "Message"].iter(),
+            tail_headers.iter(),
             tailed_rows
         )
             .block(
@@ -360,17 +863,19 @@ impl Drawable for Logs {
                             Style::default().fg(Color::White)
                         }
                     )
-                    .title(self.title.as_str())
+                    .title(tail_title.as_str())
             )
             .highlight_style(
                 Style::default()
                     .bg(Color::DarkGray)
             )
-            .widths(&[
-                Constraint::Percentage(15),
-                Constraint::Percentage(100),
-            ]);
+            .widths(&tail_widths);
         self.search_area.set_title(self.get_search_area_title());
+        self.stream_prefix_area.set_title(String::from(if self.is_stream_mode() {
+            "Stream prefix(s, not applied to the stream)"
+        } else {
+            "Stream prefix(s)"
+        }));
         let text_area = Paragraph::new(
             Text::from(log_text.as_str())
         )
@@ -379,8 +884,43 @@ impl Drawable for Logs {
                     .borders(Borders::ALL)
                     .title("full text")
             );
-        self.search_area.draw(f, chunks[0]);
-        if let SearchMode::Tail = self.search_mode {
+        let search_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(75),
+                Constraint::Percentage(25),
+            ].as_ref())
+            .split(chunks[0]);
+        self.search_area.draw(f, search_chunks[0]);
+        self.stream_prefix_area.draw(f, search_chunks[1]);
+        if self.is_insights_mode() {
+            let query_title = self.get_query_title();
+            let fields = self.query_results.get_fields().clone();
+            let labels = self.query_results.get_labels();
+            let rows = labels.iter().map(|i| Row::Data(i.iter()));
+            let widths = vec![Constraint::Percentage(100 / fields.len().max(1) as u16); fields.len().max(1)];
+            let query_table_block = Table::new(fields.iter(), rows)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(
+                            if !self.is_search_active && self.is_active {
+                                Style::default().fg(Color::Yellow)
+                            } else {
+                                Style::default().fg(Color::White)
+                            }
+                        )
+                        .title(query_title.as_str())
+                )
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                )
+                .widths(&widths);
+            if let Some(ref mut state) = self.query_results.get_state() {
+                f.render_stateful_widget(query_table_block, chunks[1], state);
+            }
+        } else if let SearchMode::Tail = self.search_mode {
             if let Some(ref mut state) = self.tailed_event_list.get_state() {
                 f.render_stateful_widget(tail_event_table_block, chunks[1], state);
             } else {
@@ -394,9 +934,29 @@ impl Drawable for Logs {
             }
         }
         f.render_widget(text_area, chunks[2]);
+        if let Some(popup) = self.range_popup.as_mut() {
+            popup.draw(f, area);
+        }
+        if let Some(popup) = self.context_popup.as_mut() {
+            if let Ok(state) = self.state.try_lock() {
+                popup.set_events(&state.context_events, state.context_highlighted, state.context_fetching);
+                popup.set_error(state.context_error.clone());
+            }
+            popup.draw(f, area);
+        }
     }
 
     async fn handle_event(&mut self, event: KeyEvent) -> bool {
+        if self.range_popup.is_some() {
+            self.handle_range_popup_event(event).await;
+            return true;
+        }
+        if let Some(popup) = &mut self.context_popup {
+            if !popup.handle_event(event).await && event.code == KeyCode::Esc {
+                self.context_popup = None;
+            }
+            return true;
+        }
         let mut solved = true;
         let is_shift = if event.modifiers == KeyModifiers::SHIFT {
             true
@@ -408,7 +968,7 @@ impl Drawable for Logs {
         } else {
             false
         };
-        if !self.search_area.is_normal_mode() {} else {
+        if !self.search_area.is_normal_mode() || !self.stream_prefix_area.is_normal_mode() {} else {
             if is_ctrl {
                 match event.code {
                     KeyCode::Char('z') => {
@@ -416,6 +976,7 @@ impl Drawable for Logs {
                             self.clear_search_mode();
                         } else {
                             self.search_mode = SearchMode::Tail;
+                            self.query_mode = QueryMode::Filter;
                         }
                         self.clear_results();
                         self.fetch_log_events();
@@ -456,14 +1017,26 @@ impl Drawable for Logs {
                         self.clear_results();
                         self.fetch_log_events();
                     },
+                    KeyCode::Char('a') => {
+                        if self.is_insights_mode() {
+                            self.query_mode = QueryMode::Filter;
+                        } else {
+                            self.query_mode = QueryMode::Insights;
+                            if self.is_tail_mode() {
+                                self.clear_search_mode();
+                            }
+                        }
+                        self.clear_results();
+                        self.fetch_log_events();
+                    },
                     KeyCode::Char('n') => {
                         if let SearchMode::Range(_, _) = self.search_mode {
                             self.clear_search_mode();
+                            self.clear_results();
+                            self.fetch_log_events();
                         } else {
-                            self.search_mode = SearchMode::Range(0, 0);
+                            self.range_popup = Some(RangePopup::new());
                         }
-                        self.clear_results();
-                        self.fetch_log_events();
                     },
                     _ => {},
                 }
@@ -471,9 +1044,15 @@ impl Drawable for Logs {
         }
         if self.is_search_active {
             // search area event handling
-            if !self.search_area.handle_event(event).await {
+            let handled = if self.is_stream_prefix_active {
+                self.stream_prefix_area.handle_event(event).await
+            } else {
+                self.search_area.handle_event(event).await
+            };
+            if !handled {
                 match event.code {
                     KeyCode::Enter => {
+                        self.commit_search();
                         self.clear_results();
                         self.fetch_log_events();
                         self.activate_logs_area();
@@ -486,6 +1065,53 @@ impl Drawable for Logs {
         } else {
             // logs area event handling
             match event.code {
+                KeyCode::Down if self.is_insights_mode() => {
+                    if is_shift {
+                        self.query_results.next_by(10);
+                    } else {
+                        self.query_results.next();
+                    }
+                },
+                KeyCode::Up if self.is_insights_mode() => {
+                    if is_shift {
+                        self.query_results.previous_by(10);
+                    } else {
+                        self.query_results.previous();
+                    }
+                },
+                // Ctrl-c changes the search range above
+                KeyCode::Char('c') if self.is_insights_mode() && event.modifiers == KeyModifiers::NONE => {
+                    self.stop_query();
+                },
+                KeyCode::Down if self.is_tail_mode() => {
+                    self.pause_tail();
+                    if is_shift {
+                        self.tailed_event_list.next_by(10);
+                    } else {
+                        self.tailed_event_list.next();
+                    }
+                },
+                KeyCode::Up if self.is_tail_mode() => {
+                    self.pause_tail();
+                    if is_shift {
+                        self.tailed_event_list.previous_by(10);
+                    } else {
+                        self.tailed_event_list.previous();
+                    }
+                },
+                KeyCode::Char('p') if self.is_tail_mode() => {
+                    if self.tail_paused {
+                        self.resume_tail();
+                    } else {
+                        self.pause_tail();
+                    }
+                },
+                KeyCode::Char(c @ '+') | KeyCode::Char(c @ '-') if self.is_tail_mode() => {
+                    self.tail_state.lock().unwrap().settings.change_interval(c == '-');
+                },
+                KeyCode::Char(c @ '>') | KeyCode::Char(c @ '<') if self.is_tail_mode() => {
+                    self.tail_state.lock().unwrap().settings.change_look_back(c == '>');
+                },
                 KeyCode::Down => {
                     if !self.is_tail_mode() {
                         if is_shift {
@@ -511,9 +1137,144 @@ impl Drawable for Logs {
                 KeyCode::Char('f') => {
                     self.activate_search_area();
                 },
+                KeyCode::Char('s') if !self.is_insights_mode() => {
+                    self.activate_stream_prefix_area();
+                },
+                KeyCode::Char('t') => {
+                    self.toggle_relative_timestamp();
+                },
+                KeyCode::Char('l') => {
+                    self.toggle_log_stream_name();
+                },
+                KeyCode::Char('c') if event.modifiers == KeyModifiers::NONE => {
+                    self.open_context();
+                },
+                KeyCode::PageUp if self.is_stream_mode() => {
+                    self.fetch_stream_page(false);
+                },
+                KeyCode::PageDown if self.is_stream_mode() => {
+                    self.fetch_stream_page(true);
+                },
                 _ => solved = false
             }
         };
         solved
     }
+
+    fn reset(&mut self) {
+        self.event_list.clear_items();
+        self.tailed_event_list.clear_items();
+        self.query_results.clear_items();
+        self.clear_cache();
+        self.clear_search_mode();
+        self.query_mode = QueryMode::Filter;
+        self.query_log_group_names = vec![];
+        self.log_group_name = None;
+        self.log_stream_name = None;
+        self.range_popup = None;
+        self.context_popup = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::{TailHandler, now_millis};
+    use crate::logsource::memory::InMemoryLogSource;
+    use rusoto_logs::FilteredLogEvent;
+    use std::sync::mpsc;
+
+    fn make_logs() -> Logs {
+        let (tx, _) = mpsc::channel();
+        Logs::new(
+            "Logs",
+            tx,
+            Arc::new(Mutex::new(GlobalState::new())),
+            Arc::new(Mutex::new(GlobalStateTail::new())),
+        )
+    }
+
+    fn make_events(timestamps: &[i64]) -> LogEventList {
+        let mut events = LogEventList::new(vec![]);
+        let items = timestamps
+            .iter()
+            .map(|t| FilteredLogEvent {
+                event_id: Some(format!("event{}", t)),
+                timestamp: Some(*t),
+                ..Default::default()
+            })
+            .collect();
+        events.insert_items(None, items);
+        events
+    }
+
+    fn get_selected_event_id(logs: &mut Logs) -> Option<String> {
+        let idx = logs.tailed_event_list.get_state()?.selected()?;
+        logs.tailed_event_list.get_event(idx)?.event_id.clone()
+    }
+
+    #[test]
+    fn tail_keeps_selection_while_paused() {
+        let mut logs = make_logs();
+        logs.update_tailed_events(&make_events(&[1, 3, 5]));
+        assert_eq!(Some(String::from("event5")), get_selected_event_id(&mut logs));
+        logs.pause_tail();
+        logs.tailed_event_list.previous();
+        logs.update_tailed_events(&make_events(&[1, 2, 3, 5, 6]));
+        assert_eq!(Some(String::from("event3")), get_selected_event_id(&mut logs));
+        assert_eq!(2, logs.tail_new_events);
+        logs.update_tailed_events(&make_events(&[1, 2, 3, 5, 6, 7]));
+        assert_eq!(3, logs.tail_new_events);
+        assert!(logs.get_tail_title().contains("[paused: 3 new events]"));
+        logs.resume_tail();
+        assert_eq!(Some(String::from("event7")), get_selected_event_id(&mut logs));
+        assert_eq!(0, logs.tail_new_events);
+        assert!(!logs.tail_paused);
+    }
+
+    #[test]
+    fn tail_follows_when_started_over() {
+        let mut logs = make_logs();
+        logs.update_tailed_events(&make_events(&[1, 3, 5]));
+        logs.pause_tail();
+        logs.update_tailed_events(&make_events(&[8]));
+        assert!(!logs.tail_paused);
+        assert_eq!(Some(String::from("event8")), get_selected_event_id(&mut logs));
+    }
+
+    #[tokio::test]
+    async fn typing_in_search_area_keeps_the_tail() {
+        let source = InMemoryLogSource::new(10);
+        source.add_log_group("group0");
+        let now = now_millis();
+        source.add_log_event("group0", "stream", now - 2000, "ERROR first");
+        let (tx, _rx) = mpsc::channel();
+        let tail_state = Arc::new(Mutex::new(GlobalStateTail::new()));
+        tail_state.lock().unwrap().settings.interval = Duration::from_secs(600);
+        let mut logs = Logs::new("Logs", tx, Arc::new(Mutex::new(GlobalState::new())), Arc::clone(&tail_state));
+        logs.log_group_name = Some(String::from("group0"));
+        logs.search_mode = SearchMode::Tail;
+        logs.sync_global_state_tail();
+        let mut handler = TailHandler::new(source.clone(), Arc::clone(&tail_state));
+        handler.poll().await;
+        assert_eq!(1, tail_state.lock().unwrap().log_events.len());
+
+        logs.activate_search_area();
+        logs.handle_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)).await;
+        for ch in "ERROR".chars() {
+            logs.handle_event(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)).await;
+            logs.sync_global_state_tail();
+            assert!(!handler.is_due());
+        }
+        assert_eq!(Some(String::new()), tail_state.lock().unwrap().log_events_filter_pattern);
+        // the engine keeps its cursor, so only the new event is added
+        source.add_log_event("group0", "stream", now - 1000, "ERROR second");
+        handler.poll().await;
+        assert_eq!(2, tail_state.lock().unwrap().log_events.len());
+
+        logs.handle_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)).await;
+        logs.sync_global_state_tail();
+        assert_eq!(Some(String::from("ERROR")), tail_state.lock().unwrap().log_events_filter_pattern);
+        assert!(handler.is_due());
+    }
 }
@@ -6,11 +6,22 @@ use tui::{
 use crossterm::event::KeyEvent;
 use std::io::Stdout;
 use async_trait::async_trait;
+use logs::SearchMode;
 
 pub mod tab;
 pub mod textinput;
 pub mod logs;
 pub mod spinner;
+pub mod selectpopup;
+pub mod rangepopup;
+pub mod contextpopup;
+
+/// request from a tab to open another tab, which is routed by `App`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Navigation {
+    /// search the log group (the current one if None) in the range
+    Logs(Option<String>, SearchMode),
+}
 
 #[async_trait]
 pub trait Drawable {
@@ -20,6 +31,20 @@ pub trait Drawable {
     /// handle event
     /// return data means whether parent component should handle event or not
     async fn handle_event(&mut self, event: KeyEvent) -> bool;
+
+    /// reset the component to the initial state (e.g. when AWS region is changed)
+    fn reset(&mut self) {}
+
+    /// navigation requested while handling the last event
+    fn take_navigation(&mut self) -> Option<Navigation> {
+        None
+    }
+
+    /// open what the navigation points to
+    /// return data means whether the component is the destination or not
+    fn navigate(&mut self, _navigation: &Navigation) -> bool {
+        false
+    }
 }
 
 
@@ -0,0 +1,113 @@
+use crate::components::{
+    Drawable,
+    textinput::{TextInputComponent, InputMode},
+};
+use crate::utils;
+use anyhow::Result;
+use tui::{
+    backend::CrosstermBackend,
+    layout::{Layout, Constraint, Direction, Rect},
+    text::Text,
+    widgets::{Block, Borders, Clear, Paragraph},
+    style::{Style, Color},
+    Frame,
+};
+use crossterm::event::{KeyEvent, KeyCode};
+use std::io::Stdout;
+use std::time::{SystemTime, UNIX_EPOCH};
+use async_trait::async_trait;
+
+const TIME_HINT: &str = "e.g. 2020-12-12-01:01:00, 2020-12-12T01:01:00Z, -2h, yesterday 09:00";
+
+/// popup to input the start and the end of an absolute range
+pub struct RangePopup {
+    start: TextInputComponent,
+    end: TextInputComponent,
+    is_start_active: bool,
+    error: Option<String>,
+}
+
+impl RangePopup {
+    pub fn new() -> Self {
+        let mut popup = Self {
+            start: TextInputComponent::new("Start", TIME_HINT),
+            end: TextInputComponent::new("End", TIME_HINT),
+            is_start_active: true,
+            error: None,
+        };
+        popup.start.set_input_mode(InputMode::EditMode);
+        popup.end.set_input_mode(InputMode::EditMode);
+        popup.start.select();
+        popup
+    }
+
+    fn toggle_active(&mut self) {
+        self.is_start_active = !self.is_start_active;
+        self.start.toggle_active();
+        self.end.toggle_active();
+    }
+
+    /// validated range in epoch millis
+    pub fn get_range(&self) -> Result<(i64, i64)> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+        utils::parse_range(self.start.get_text(), self.end.get_text(), now)
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+}
+
+impl Default for RangePopup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Drawable for RangePopup {
+    fn draw(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        let area = utils::centered_rect(60, 40, area);
+        f.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title("Range (Tab: switch, Enter: apply, Esc: cancel)");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(1),
+            ].as_ref())
+            .split(inner);
+        self.start.draw(f, chunks[0]);
+        self.end.draw(f, chunks[1]);
+        if let Some(error) = &self.error {
+            let error = Paragraph::new(Text::from(error.as_str()))
+                .style(Style::default().fg(Color::Red));
+            f.render_widget(error, chunks[2]);
+        }
+    }
+
+    /// Enter and Esc are left to the parent
+    async fn handle_event(&mut self, event: KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Enter | KeyCode::Esc => false,
+            KeyCode::Tab | KeyCode::Up | KeyCode::Down => {
+                self.toggle_active();
+                true
+            },
+            _ => {
+                self.error = None;
+                if self.is_start_active {
+                    self.start.handle_event(event).await
+                } else {
+                    self.end.handle_event(event).await
+                }
+            },
+        }
+    }
+}
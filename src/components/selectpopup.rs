@@ -0,0 +1,117 @@
+use crate::components::Drawable;
+use crate::utils::{self, StatefulList};
+use tui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    widgets::{
+        Block,
+        Borders,
+        Clear,
+        List,
+        ListItem,
+        ListState,
+    },
+    style::{Style, Modifier, Color},
+    Frame,
+};
+use crossterm::event::{KeyEvent, KeyCode};
+use std::io::Stdout;
+use async_trait::async_trait;
+
+/// popup which lets the user choose one of the items
+pub struct SelectPopup {
+    title: String,
+    items: Vec<String>,
+    state: Option<ListState>,
+}
+
+impl SelectPopup {
+    pub fn new(title: &str, items: Vec<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            state: Some(ListState::default()),
+        }
+    }
+
+    /// select the item which has the same label (e.g. current value)
+    pub fn select_item(&mut self, label: &str) {
+        let mut state = ListState::default();
+        state.select(self.items.iter().position(|item| item == label));
+        self.set_state(state);
+    }
+
+    pub fn get_selected_item(&self) -> Option<String> {
+        if let Some(state) = &self.state {
+            if let Some(idx) = state.selected() {
+                return self.items.get(idx).cloned();
+            }
+        }
+        None
+    }
+}
+
+impl StatefulList for SelectPopup {
+    fn get_labels(&self) -> Vec<String> {
+        self.items.clone()
+    }
+    fn get_state(&mut self) -> Option<ListState> {
+        self.state.clone()
+    }
+    fn set_state(&mut self, new_state: ListState) {
+        self.state = Some(new_state);
+    }
+}
+
+#[async_trait]
+impl Drawable for SelectPopup {
+    fn draw(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        let area = utils::centered_rect(50, 60, area);
+        let items: Vec<ListItem> = self.items.iter()
+            .map(|i| ListItem::new(i.as_ref())).collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(self.title.as_str())
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        f.render_widget(Clear, area);
+        if let Some(ref mut state) = self.state.clone() {
+            f.render_stateful_widget(list, area, state);
+        }
+    }
+
+    async fn handle_event(&mut self, event: KeyEvent) -> bool {
+        let mut solved = true;
+        match event.code {
+            KeyCode::Down => self.next(),
+            KeyCode::Up => self.previous(),
+            _ => solved = false
+        }
+        solved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_select_item_by_label() {
+        let mut popup = SelectPopup::new("test", vec![
+            String::from("a"),
+            String::from("b"),
+        ]);
+        assert_eq!(None, popup.get_selected_item());
+        popup.select_item("b");
+        assert_eq!(Some(String::from("b")), popup.get_selected_item());
+        popup.select_item("unknown");
+        assert_eq!(None, popup.get_selected_item());
+    }
+}
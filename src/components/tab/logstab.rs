@@ -1,8 +1,14 @@
 use crate::components::{
     Drawable,
+    Navigation,
     logs::Logs,
 };
-use crate::utils::{StatefulList, loggroup_menulist::LogGroupMenuList};
+use crate::utils::{
+    StatefulList,
+    loggroup_menulist::LogGroupMenuList,
+    logstream_menulist::LogStreamMenuList,
+    timestamp_format::TimestampFormat,
+};
 use tui::{
     backend::CrosstermBackend,
     widgets::{
@@ -34,27 +40,38 @@ pub struct LogsTab
     log_groups: LogGroupMenuList,
     is_menu_active: bool,
     log_area: Logs,
-    // tx: Sender<Instruction>,
+    tx: Sender<Instruction>,
     state: Arc<Mutex<GlobalState>>,
-    // tail_state: Arc<Mutex<GlobalStateTail>>,
     query: Option<String>,
+    /// log group whose streams are listed in the menu instead of the log groups
+    stream_group: Option<String>,
+    log_streams: LogStreamMenuList,
+    stream_query: Option<String>,
 }
 
 impl LogsTab {
-    pub async fn new(log_groups: LogGroupMenuList, tx: Sender<Instruction>, state: Arc<Mutex<GlobalState>>, tail_state: Arc<Mutex<GlobalStateTail>>) -> Result<LogsTab> {
+    pub async fn new(
+        log_groups: LogGroupMenuList,
+        tx: Sender<Instruction>,
+        state: Arc<Mutex<GlobalState>>,
+        tail_state: Arc<Mutex<GlobalStateTail>>,
+        timestamp_format: TimestampFormat,
+    ) -> Result<LogsTab> {
         let child_tx = Sender::clone(&tx);
         let child_tx2 = Sender::clone(&tx);
         let child_state = Arc::clone(&state);
-        let child_tail_state = Arc::clone(&tail_state);
-        let tab = LogsTab {
+        let mut tab = LogsTab {
             log_groups,
             is_menu_active: true,
-            log_area: Logs::new("Logs", child_tx2, child_state, child_tail_state),
-            // tx,
+            log_area: Logs::new("Logs", child_tx2, child_state, tail_state),
+            tx,
             state,
-            // tail_state,
             query: None,
+            stream_group: None,
+            log_streams: LogStreamMenuList::new(vec![]),
+            stream_query: None,
         };
+        tab.log_area.set_timestamp_format(timestamp_format);
         // tab.fetch_log_groups().await?;
         child_tx.send(Instruction::FetchLogGroups)?;
         Ok(tab)
@@ -85,6 +102,107 @@ impl LogsTab {
             self.query = Some(query.clone());
         }
     }
+
+    /// list the log streams of the selected log group in the menu
+    fn open_log_streams(&mut self) {
+        let idx = match self.log_groups.get_state().and_then(|s| s.selected()) {
+            Some(idx) => idx,
+            None => return,
+        };
+        if let Some(log_group_name) = self.log_groups.get_log_group_name(idx) {
+            self.stream_group = Some(log_group_name);
+            self.stream_query = None;
+            self.fetch_log_streams();
+        }
+    }
+
+    fn close_log_streams(&mut self) {
+        self.stream_group = None;
+        self.stream_query = None;
+        self.log_streams = LogStreamMenuList::new(vec![]);
+    }
+
+    fn fetch_log_streams(&mut self) {
+        if let Some(log_group_name) = &self.stream_group {
+            self.log_streams = LogStreamMenuList::new(vec![]);
+            self.tx.send(Instruction::FetchLogStreams(log_group_name.clone(), self.stream_query.clone())).unwrap();
+        }
+    }
+
+    /// open the events of the selected log stream in the log area
+    fn open_log_stream(&mut self) {
+        if let Some(log_stream_name) = self.log_streams.get_selected_log_stream_name() {
+            self.log_area.set_log_group_name(self.stream_group.clone());
+            self.log_area.set_log_stream_name(Some(log_stream_name));
+            self.activate_log_area();
+            self.log_area.clear_results();
+            self.log_area.fetch_log_events();
+        }
+    }
+
+    fn handle_log_streams_event(&mut self, event: KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Down => self.log_streams.next(),
+            KeyCode::Up => self.log_streams.previous(),
+            KeyCode::Enter => self.open_log_stream(),
+            KeyCode::Left | KeyCode::Esc => self.close_log_streams(),
+            KeyCode::Char(ch) => {
+                self.stream_query.get_or_insert_with(String::new).push(ch);
+                self.fetch_log_streams();
+            },
+            KeyCode::Backspace => {
+                if let Some(query) = &mut self.stream_query {
+                    query.pop();
+                    if query.is_empty() {
+                        self.stream_query = None;
+                    }
+                    self.fetch_log_streams();
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn draw_log_streams(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, log_group_name: String) {
+        let mut fetching = true;
+        let mut error = None;
+        if let Ok(m_guard) = self.state.try_lock() {
+            let filter = Some((log_group_name.clone(), self.stream_query.clone()));
+            if m_guard.log_streams_filter == filter {
+                self.log_streams = m_guard.log_streams.clone_with_state(self.log_streams.get_state());
+                fetching = m_guard.log_streams_fetching;
+                error = m_guard.log_streams_error.clone();
+            }
+        }
+        let labels = self.log_streams.get_labels();
+        let items: Vec<ListItem> = labels.iter().map(|i| ListItem::new(i.as_ref())).collect();
+        let title = match (&self.stream_query, fetching, error) {
+            (_, true, _) => format!("Log Streams {} [Fetching ...]", log_group_name),
+            (_, false, Some(error)) => format!("Log Streams {} [Error: {}]", log_group_name, error),
+            (Some(query), false, None) => format!("Log Streams {} [{}]", log_group_name, query),
+            (None, false, None) => format!("Log Streams {}", log_group_name),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(
+                if self.is_menu_active {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::White)
+                }
+            )
+            .title(title);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        let mut state = self.log_streams.get_state().unwrap_or_default();
+        f.render_stateful_widget(list, area, &mut state);
+    }
 }
 
 #[async_trait]
@@ -97,8 +215,15 @@ impl Drawable for LogsTab {
                 Constraint::Percentage(70),
             ].as_ref())
             .split(area);
+        if let Some(log_group_name) = self.stream_group.clone() {
+            self.draw_log_streams(f, chunks[0], log_group_name);
+            self.log_area.draw(f, chunks[1]);
+            return;
+        }
         let labels = if let Ok(m_guard) = self.state.try_lock() {
+            let selected = self.log_groups.get_selected_log_group_names();
             self.log_groups = m_guard.log_groups.clone_with_state(self.log_groups.get_state());
+            self.log_groups.set_selected_log_group_names(selected);
             if let Some(query) = &self.query {
                 self.log_groups.filter_items(query.as_str());
             }
@@ -119,7 +244,9 @@ impl Drawable for LogsTab {
             );
         let block = if let Ok(s) = self.state.try_lock() {
             if !s.log_groups_fething {
-                if let Some(query) = &self.query {
+                if let Some(error) = &s.log_groups_error {
+                    block.title(format!("Log Groups [Error: {}]", error))
+                } else if let Some(query) = &self.query {
                     block.title("Log Groups [".to_owned() + query.as_str() + "]")
                 } else {
                     block.title("Log Groups")
@@ -148,6 +275,9 @@ impl Drawable for LogsTab {
 
     async fn handle_event(&mut self, event: KeyEvent) -> bool {
         let mut solved = true;
+        if self.is_menu_active && self.stream_group.is_some() {
+            return self.handle_log_streams_event(event);
+        }
         if self.is_menu_active {
             match event.code {
                 KeyCode::Down => {
@@ -158,6 +288,7 @@ impl Drawable for LogsTab {
                     if let Some(state) = self.log_groups.get_state() {
                         if let Some(idx) = state.selected() {
                             self.log_area.set_log_group_name(self.log_groups.get_log_group_name(idx));
+                            self.log_area.set_log_stream_name(None);
                             self.activate_log_area();
                             if let Some(_) = self.log_area.get_log_group_name() {
                                 self.log_area.fetch_log_events();
@@ -165,6 +296,16 @@ impl Drawable for LogsTab {
                         }
                     }
                 },
+                KeyCode::Right => self.open_log_streams(),
+                KeyCode::Char(' ') => {
+                    // log group names never contain spaces
+                    if let Some(state) = self.log_groups.get_state() {
+                        if let Some(idx) = state.selected() {
+                            self.log_groups.toggle_selection(idx);
+                            self.log_area.set_query_log_group_names(self.log_groups.get_selected_log_group_names());
+                        }
+                    }
+                },
                 KeyCode::Char(ch) => {
                     self.push_char_to_query(ch);
                 },
@@ -183,4 +324,32 @@ impl Drawable for LogsTab {
         }
         solved
     }
+
+    fn navigate(&mut self, navigation: &Navigation) -> bool {
+        match navigation {
+            Navigation::Logs(log_group_name, search_mode) => {
+                if log_group_name.is_some() {
+                    self.log_area.set_log_group_name(log_group_name.clone());
+                }
+                self.log_area.set_log_stream_name(None);
+                self.log_area.set_search_mode(search_mode.clone());
+                if self.log_area.get_log_group_name().is_some() {
+                    self.activate_log_area();
+                    self.log_area.clear_results();
+                    self.log_area.fetch_log_events();
+                } else {
+                    self.activate_menu_area();
+                }
+                true
+            },
+        }
+    }
+
+    fn reset(&mut self) {
+        self.log_groups = LogGroupMenuList::new(vec![]);
+        self.query = None;
+        self.close_log_streams();
+        self.log_area.reset();
+        self.activate_menu_area();
+    }
 }
@@ -1,42 +1,750 @@
-use crate::components::Drawable;
+use crate::components::{Drawable, Navigation, logs::SearchMode};
+use crate::utils::{
+    StatefulList,
+    alarm_list::{AlarmList, get_alarm_metric, get_alarm_detail_text},
+    metric_menulist::{MetricLevel, MetricMenuList},
+    metric_series::{
+        MetricSeries,
+        TimeWindow,
+        get_series_label,
+        get_value_bounds,
+        guess_log_group_name,
+        format_time_label,
+    },
+};
+use crate::instruction::Instruction;
+use crate::globalstate::GlobalStateMetrics;
+use crate::aws::AwsSettings;
 use tui::{
     backend::CrosstermBackend,
+    symbols,
+    text::{Span, Text},
     widgets::{
+        Axis,
         Block,
         Borders,
+        Chart,
+        Dataset,
+        GraphType,
+        List,
+        ListItem,
+        Paragraph,
     },
     layout::{
         Layout,
+        Direction,
         Constraint,
         Rect,
     },
+    style::{Style, Modifier, Color},
     Frame,
 };
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, KeyCode, KeyModifiers};
+use rusoto_cloudwatch::{Metric, MetricAlarm};
 use std::io::Stdout;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use async_trait::async_trait;
 
-pub struct MetricsTab {}
+const STATISTICS: [&str; 5] = ["Average", "Sum", "Minimum", "Maximum", "SampleCount"];
+/// periods in seconds
+const PERIODS: [i64; 4] = [60, 300, 900, 3600];
+const SERIES_COLORS: [Color; 6] = [Color::Yellow, Color::Cyan, Color::Magenta, Color::Green, Color::Red, Color::Blue];
+
+/// list shown on the left side
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pane {
+    Metrics,
+    Alarms,
+}
+
+fn get_alarm_state_color(state: Option<&str>) -> Color {
+    match state {
+        Some("OK") => Color::Green,
+        Some("ALARM") => Color::Red,
+        _ => Color::Gray,
+    }
+}
+
+/// browser of CloudWatch metrics (namespace -> metric name -> dimension set)
+/// and the chart of the selected metrics, or the alarms and their history
+pub struct MetricsTab {
+    pane: Pane,
+    menu: MetricMenuList,
+    query: Option<String>,
+    alarms: AlarmList,
+    /// name prefix which the alarms are filtered by
+    alarm_prefix: Option<String>,
+    /// metrics overlaid on the chart
+    chart_metrics: Vec<Metric>,
+    statistic_idx: usize,
+    period_idx: usize,
+    /// period of the opened alarm which isn't in `PERIODS`. it's used until the period is changed
+    alarm_period: Option<i64>,
+    range: SearchMode,
+    /// range which the chart data is fetched with
+    chart_range: (i64, i64),
+    /// window selected on the chart to open in the logs tab
+    window: Option<TimeWindow>,
+    navigation: Option<Navigation>,
+    tx: Sender<Instruction>,
+    metrics_state: Arc<Mutex<GlobalStateMetrics>>,
+    settings: Arc<Mutex<AwsSettings>>,
+    /// settings which the metrics are fetched with. None means not fetched yet
+    fetched_settings: Option<AwsSettings>,
+    /// namespaces fetched once. listing them pages through every metric of the account
+    namespaces: Option<Vec<Metric>>,
+}
 
 impl MetricsTab {
-    pub fn new() -> MetricsTab {
-        MetricsTab {}
+    pub fn new(
+        tx: Sender<Instruction>,
+        metrics_state: Arc<Mutex<GlobalStateMetrics>>,
+        settings: Arc<Mutex<AwsSettings>>,
+    ) -> MetricsTab {
+        MetricsTab {
+            pane: Pane::Metrics,
+            menu: MetricMenuList::new(vec![], MetricLevel::Namespace),
+            query: None,
+            alarms: AlarmList::new(vec![]),
+            alarm_prefix: None,
+            chart_metrics: vec![],
+            statistic_idx: 0,
+            period_idx: 0,
+            alarm_period: None,
+            range: SearchMode::OneH,
+            chart_range: (0, 0),
+            window: None,
+            navigation: None,
+            tx,
+            metrics_state,
+            settings,
+            fetched_settings: None,
+            namespaces: None,
+        }
+    }
+
+    /// the state is marked as fetching before sending,
+    /// so that the metrics of the previous level aren't taken as the result
+    fn fetch_metrics(&self) {
+        let (namespace, metric_name) = self.menu.get_level().get_filter();
+        {
+            let mut state = self.metrics_state.lock().unwrap();
+            state.metrics_fetching = true;
+            state.metrics = vec![];
+            state.metrics_filter = (namespace.clone(), metric_name.clone());
+        }
+        self.tx.send(Instruction::FetchMetrics(namespace, metric_name, None)).unwrap();
+    }
+
+    /// fetch the rest of the truncated metrics of the level
+    fn fetch_more_metrics(&self) {
+        let (namespace, metric_name) = self.menu.get_level().get_filter();
+        let token = {
+            let mut state = self.metrics_state.lock().unwrap();
+            if state.metrics_fetching || state.metrics_filter != (namespace.clone(), metric_name.clone()) {
+                return;
+            }
+            let token = match state.metrics_next_token.take() {
+                Some(token) => token,
+                None => return,
+            };
+            state.metrics_fetching = true;
+            token
+        };
+        self.tx.send(Instruction::FetchMetrics(namespace, metric_name, Some(token))).unwrap();
+    }
+
+    /// fetch the namespaces at first, and again whenever the profile or the region changes
+    fn fetch_if_needed(&mut self) {
+        let settings = self.settings.lock().unwrap().clone();
+        if self.fetched_settings.as_ref() != Some(&settings) {
+            if self.fetched_settings.is_some() {
+                self.reset();
+            }
+            self.fetched_settings = Some(settings);
+            self.fetch_metrics();
+        }
+    }
+
+    fn set_level(&mut self, level: MetricLevel) {
+        self.query = None;
+        match &self.namespaces {
+            Some(namespaces) if level == MetricLevel::Namespace => {
+                self.menu = MetricMenuList::new(namespaces.clone(), level);
+            },
+            _ => {
+                self.menu = MetricMenuList::new(vec![], level);
+                self.fetch_metrics();
+            },
+        }
+    }
+
+    fn enter(&mut self) {
+        let metric = match self.menu.get_selected_metric() {
+            Some(metric) => metric,
+            None => return,
+        };
+        let namespace = metric.namespace.clone().unwrap_or_default();
+        let metric_name = metric.metric_name.clone().unwrap_or_default();
+        match self.menu.get_level() {
+            MetricLevel::Namespace => self.set_level(MetricLevel::MetricName(namespace)),
+            MetricLevel::MetricName(_) => self.set_level(MetricLevel::Dimensions(namespace, metric_name)),
+            MetricLevel::Dimensions(_, _) => self.toggle_chart_metric(metric),
+        }
+    }
+
+    /// add the metric to the chart, or remove it if it's already there
+    fn toggle_chart_metric(&mut self, metric: Metric) {
+        match self.chart_metrics.iter().position(|m| m == &metric) {
+            Some(idx) => {
+                self.chart_metrics.remove(idx);
+            },
+            None => self.chart_metrics.push(metric),
+        }
+        self.fetch_metric_data();
+    }
+
+    fn fetch_metric_data(&mut self) {
+        if self.chart_metrics.is_empty() {
+            self.metrics_state.lock().unwrap().metric_data = vec![];
+            return;
+        }
+        self.chart_range = self.range.get_range();
+        self.window = None;
+        self.tx.send(Instruction::FetchMetricData(
+            self.chart_metrics.clone(),
+            STATISTICS[self.statistic_idx].to_string(),
+            self.get_period(),
+            self.chart_range.0,
+            self.chart_range.1,
+        )).unwrap();
+    }
+
+    /// handle the keys to change the chart. returns false if the key isn't for the chart
+    fn handle_chart_event(&mut self, event: KeyEvent) -> bool {
+        if event.modifiers != KeyModifiers::CONTROL {
+            return false;
+        }
+        match event.code {
+            KeyCode::Char('x') => self.range = SearchMode::OneM,
+            KeyCode::Char('c') => self.range = SearchMode::ThirtyM,
+            KeyCode::Char('v') => self.range = SearchMode::OneH,
+            KeyCode::Char('b') => self.range = SearchMode::TwelveH,
+            KeyCode::Char('s') => self.statistic_idx = (self.statistic_idx + 1) % STATISTICS.len(),
+            KeyCode::Char('t') => {
+                self.period_idx = (self.period_idx + 1) % PERIODS.len();
+                self.alarm_period = None;
+            },
+            KeyCode::Char('d') => self.chart_metrics.clear(),
+            _ => return false,
+        }
+        self.fetch_metric_data();
+        true
+    }
+
+    fn get_period(&self) -> i64 {
+        self.alarm_period.unwrap_or(PERIODS[self.period_idx])
+    }
+
+    fn get_period_millis(&self) -> i64 {
+        self.get_period() * 1000
+    }
+
+    /// move the window by a period. the window starts at the peak of the chart
+    fn move_window(&mut self, forward: bool) {
+        let period = self.get_period_millis();
+        self.window = Some(match self.window {
+            Some(window) if forward => window.shift(period, self.chart_range),
+            Some(window) => window.shift(-period, self.chart_range),
+            None => {
+                let series = self.metrics_state.lock().unwrap().metric_data.clone();
+                TimeWindow::at_peak(&series, period, self.chart_range)
+            },
+        });
+    }
+
+    fn resize_window(&mut self, widen: bool) {
+        let period = self.get_period_millis();
+        if let Some(window) = self.window {
+            let step = if widen { period } else { -period };
+            self.window = Some(window.resize(step, period, self.chart_range));
+        }
+    }
+
+    /// open the logs of the window, or of the whole chart if no window is selected
+    fn open_logs(&mut self) {
+        let (start, end) = match self.window {
+            Some(window) => (window.start, window.end),
+            None => self.chart_range,
+        };
+        let log_group_name = self.chart_metrics.iter().find_map(guess_log_group_name);
+        self.navigation = Some(Navigation::Logs(log_group_name, SearchMode::Range(start, end)));
+    }
+
+    /// handle the keys to select the window on the chart
+    fn handle_window_event(&mut self, event: KeyEvent) -> bool {
+        if self.chart_metrics.is_empty() {
+            return false;
+        }
+        match (event.modifiers, event.code) {
+            (KeyModifiers::NONE, KeyCode::Left) => self.move_window(false),
+            (KeyModifiers::NONE, KeyCode::Right) => self.move_window(true),
+            (KeyModifiers::CONTROL, KeyCode::Char('w')) => self.resize_window(true),
+            (KeyModifiers::CONTROL, KeyCode::Char('n')) => self.resize_window(false),
+            (KeyModifiers::CONTROL, KeyCode::Char('l')) => self.open_logs(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_chart_title(&self, fetching: bool, error: Option<String>) -> String {
+        let mut title = format!(
+            "Chart - {} / {}s / {}",
+            STATISTICS[self.statistic_idx],
+            self.get_period(),
+            self.range.get_label(),
+        );
+        if let Some(window) = self.window {
+            let range = self.chart_range.1 - self.chart_range.0;
+            title = format!(
+                "{} - Window: {}~{}",
+                title,
+                format_time_label(window.start, range),
+                format_time_label(window.end, range),
+            );
+        }
+        match error {
+            Some(error) => format!("{} [{}]", title, error),
+            None if fetching => format!("{} [Fetching ...]", title),
+            None => title,
+        }
+    }
+
+    fn draw_chart(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        if self.chart_metrics.is_empty() {
+            f.render_widget(Block::default().borders(Borders::ALL).title("Chart"), area);
+            return;
+        }
+        let (series, fetching, error) = match self.metrics_state.try_lock() {
+            Ok(m_guard) => (m_guard.metric_data.clone(), m_guard.metric_data_fetching, m_guard.metric_data_error.clone()),
+            Err(_) => (vec![], true, None),
+        };
+        let series: Vec<MetricSeries> = series
+            .into_iter()
+            .filter(|s| self.chart_metrics.iter().any(|m| get_series_label(m) == s.label))
+            .collect();
+        let [min, max] = get_value_bounds(&series);
+        // the edges of the window are drawn as vertical lines
+        let edges: Vec<Vec<(f64, f64)>> = self.window
+            .iter()
+            .flat_map(|w| vec![w.start, w.end])
+            .map(|x| vec![(x as f64, min), (x as f64, max)])
+            .collect();
+        let mut datasets: Vec<Dataset> = series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| Dataset::default()
+                .name(s.label.as_str())
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(SERIES_COLORS[i % SERIES_COLORS.len()]))
+                .data(&s.points)
+            )
+            .collect();
+        for edge in &edges {
+            datasets.push(Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::White))
+                .data(edge)
+            );
+        }
+        let (start, end) = self.chart_range;
+        let x_labels = vec![
+            Span::raw(format_time_label(start, end - start)),
+            Span::raw(format_time_label(start + (end - start) / 2, end - start)),
+            Span::raw(format_time_label(end, end - start)),
+        ];
+        let y_labels = vec![
+            Span::raw(format!("{:.2}", min)),
+            Span::raw(format!("{:.2}", (min + max) / 2.0)),
+            Span::raw(format!("{:.2}", max)),
+        ];
+        let title = self.get_chart_title(fetching, error);
+        let chart = Chart::new(datasets)
+            .block(Block::default().borders(Borders::ALL).title(title.as_str()))
+            .x_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([start as f64, end as f64])
+                    .labels(x_labels)
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([min, max])
+                    .labels(y_labels)
+            );
+        f.render_widget(chart, area);
+    }
+
+    fn toggle_pane(&mut self) {
+        self.pane = match self.pane {
+            Pane::Metrics => {
+                self.tx.send(Instruction::FetchAlarms).unwrap();
+                Pane::Alarms
+            },
+            Pane::Alarms => Pane::Metrics,
+        };
+    }
+
+    fn fetch_alarm_history(&self) {
+        if let Some(alarm_name) = self.alarms.get_selected_alarm().and_then(|a| a.alarm_name) {
+            self.tx.send(Instruction::FetchAlarmHistory(alarm_name)).unwrap();
+        }
+    }
+
+    /// chart the metric of the selected alarm with its statistic and period
+    fn open_alarm_metric(&mut self) {
+        let alarm = match self.alarms.get_selected_alarm() {
+            Some(alarm) => alarm,
+            None => return,
+        };
+        let metric = match get_alarm_metric(&alarm) {
+            Some(metric) => metric,
+            None => return,
+        };
+        if let Some(idx) = STATISTICS.iter().position(|s| Some(s.to_string()) == alarm.statistic) {
+            self.statistic_idx = idx;
+        }
+        // the period of metric math alarms is in the metric stat
+        let period = alarm.period.or_else(|| {
+            alarm.metrics.as_ref()?.iter().filter_map(|q| q.metric_stat.as_ref()).map(|s| s.period).next()
+        });
+        if let Some(period) = period {
+            match PERIODS.iter().position(|p| *p == period) {
+                Some(idx) => {
+                    self.period_idx = idx;
+                    self.alarm_period = None;
+                },
+                None => self.alarm_period = Some(period),
+            }
+        }
+        self.chart_metrics = vec![metric];
+        self.pane = Pane::Metrics;
+        self.fetch_metric_data();
+    }
+
+    fn set_alarm_prefix(&mut self, prefix: Option<String>) {
+        self.alarm_prefix = prefix.filter(|p| !p.is_empty());
+        self.alarms.set_prefix(self.alarm_prefix.clone());
+    }
+
+    fn handle_alarms_event(&mut self, event: KeyEvent) -> bool {
+        if event.modifiers == KeyModifiers::CONTROL {
+            match event.code {
+                KeyCode::Char('o') => self.open_alarm_metric(),
+                KeyCode::Char('f') => self.alarms.cycle_state_filter(),
+                _ => return false,
+            }
+            return true;
+        }
+        match event.code {
+            KeyCode::Down => self.alarms.next(),
+            KeyCode::Up => self.alarms.previous(),
+            KeyCode::Enter => self.fetch_alarm_history(),
+            KeyCode::Esc => self.pane = Pane::Metrics,
+            KeyCode::Backspace => {
+                let mut prefix = self.alarm_prefix.clone().unwrap_or_default();
+                prefix.pop();
+                self.set_alarm_prefix(Some(prefix));
+            },
+            KeyCode::Char(ch) => {
+                let mut prefix = self.alarm_prefix.clone().unwrap_or_default();
+                prefix.push(ch);
+                self.set_alarm_prefix(Some(prefix));
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_alarms_title(&self, fetching: bool, error: Option<String>) -> String {
+        let mut title = String::from("Alarms");
+        if let Some(state) = self.alarms.get_state_filter() {
+            title = format!("{} ({})", title, state);
+        }
+        if fetching {
+            format!("{} [Fetching ...]", title)
+        } else if let Some(error) = error {
+            format!("{} [Error: {}]", title, error)
+        } else if let Some(prefix) = &self.alarm_prefix {
+            format!("{} [{}*]", title, prefix)
+        } else {
+            title
+        }
+    }
+
+    fn draw_alarms(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        let (fetching, error) = match self.metrics_state.try_lock() {
+            Ok(m_guard) => {
+                if !self.alarms.is_same_items(&m_guard.alarms) {
+                    self.alarms.set_items(m_guard.alarms.clone());
+                }
+                (m_guard.alarms_fetching, m_guard.alarms_error.clone())
+            },
+            Err(_) => (true, None),
+        };
+        let alarms = self.alarms.get_alarms();
+        let labels = self.alarms.get_labels();
+        let items: Vec<ListItem> = alarms
+            .iter()
+            .zip(labels.iter())
+            .map(|(alarm, label)| ListItem::new(label.as_str())
+                .style(Style::default().fg(get_alarm_state_color(alarm.state_value.as_deref())))
+            )
+            .collect();
+        let title = self.get_alarms_title(fetching, error);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(title.as_str())
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        if let Some(ref mut state) = self.alarms.get_state() {
+            f.render_stateful_widget(list, area, state);
+        }
+    }
+
+    fn draw_alarm_detail(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        let alarm = match self.alarms.get_selected_alarm() {
+            Some(alarm) => alarm,
+            None => {
+                f.render_widget(Block::default().borders(Borders::ALL).title("Alarm"), area);
+                return;
+            },
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(7),
+                Constraint::Min(3),
+            ].as_ref())
+            .split(area);
+        let text = get_alarm_detail_text(&alarm);
+        let detail = Paragraph::new(Text::from(text.as_str()))
+            .style(Style::default().fg(get_alarm_state_color(alarm.state_value.as_deref())))
+            .block(Block::default().borders(Borders::ALL).title("Alarm"));
+        f.render_widget(detail, chunks[0]);
+        let history = self.get_alarm_history(&alarm);
+        let items: Vec<ListItem> = history.iter().map(|h| ListItem::new(h.as_str())).collect();
+        let title = if history.is_empty() {
+            "History (Enter: fetch)"
+        } else {
+            "History"
+        };
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)), chunks[1]);
+    }
+
+    /// "timestamp summary" lines of the fetched history of the alarm
+    fn get_alarm_history(&self, alarm: &MetricAlarm) -> Vec<String> {
+        let m_guard = match self.metrics_state.try_lock() {
+            Ok(m_guard) => m_guard,
+            Err(_) => return vec![],
+        };
+        match &m_guard.alarm_history {
+            Some((alarm_name, items)) if Some(alarm_name) == alarm.alarm_name.as_ref() => items
+                .iter()
+                .map(|i| format!(
+                    "{} {}",
+                    i.timestamp.clone().unwrap_or_default(),
+                    i.history_summary.clone().unwrap_or_default(),
+                ))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// go back to the upper level. returns false at the top level
+    fn back(&mut self) -> bool {
+        match self.menu.get_level().clone() {
+            MetricLevel::Namespace => return false,
+            MetricLevel::MetricName(_) => self.set_level(MetricLevel::Namespace),
+            MetricLevel::Dimensions(namespace, _) => self.set_level(MetricLevel::MetricName(namespace)),
+        }
+        true
+    }
+
+    fn push_char_to_query(&mut self, ch: char) {
+        let mut query = self.query.take().unwrap_or_default();
+        query.push(ch);
+        self.query = Some(query);
+        self.menu.set_query(self.query.clone());
+    }
+
+    fn pop_char_from_query(&mut self) {
+        if let Some(mut query) = self.query.take() {
+            query.pop();
+            if !query.is_empty() {
+                self.query = Some(query);
+            }
+        }
+        self.menu.set_query(self.query.clone());
+    }
+
+    fn get_menu_title(&self, fetching: bool, error: Option<String>, truncated: bool) -> String {
+        let mut title = match self.menu.get_level() {
+            MetricLevel::Namespace => String::from("Namespaces"),
+            MetricLevel::MetricName(namespace) => format!("Metrics [{}]", namespace),
+            MetricLevel::Dimensions(namespace, metric_name) => {
+                format!("Dimensions [{} > {}]", namespace, metric_name)
+            },
+        };
+        if truncated {
+            title = format!("{} (truncated, Ctrl-e: more)", title);
+        }
+        if fetching {
+            format!("{} [Fetching ...]", title)
+        } else if let Some(error) = error {
+            format!("{} [Error: {}]", title, error)
+        } else if let Some(query) = &self.query {
+            format!("{} [{}]", title, query)
+        } else {
+            title
+        }
+    }
+
+    fn get_help_text(&self) -> String {
+        if self.pane == Pane::Alarms {
+            String::from("Enter: history, Ctrl-o: chart the metric, Ctrl-f: filter by state, type to filter by prefix, Ctrl-a/Esc: metrics")
+        } else if self.chart_metrics.is_empty() {
+            String::from("Enter: open / add the metric to the chart, Esc: back, type to filter, Ctrl-e: more, Ctrl-a: alarms")
+        } else {
+            String::from("Enter: add/remove, Ctrl-x/c/v/b: 1m/15m/1h/12h, Ctrl-s: statistic, Ctrl-t: period, Ctrl-d: clear\n\
+                Left/Right: move window, Ctrl-w/n: widen/narrow window, Ctrl-l: open logs of the window")
+        }
     }
 }
 
 #[async_trait]
 impl Drawable for MetricsTab {
     fn draw(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        self.fetch_if_needed();
         let chunks = Layout::default()
+            .direction(Direction::Horizontal)
             .constraints([
-                Constraint::Percentage(100)
+                Constraint::Percentage(30),
+                Constraint::Percentage(70),
             ].as_ref())
             .split(area);
-        let block = Block::default().borders(Borders::ALL).title("Metrics Tab Area");
-        f.render_widget(block, chunks[0]);
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(10),
+                Constraint::Length(4),
+            ].as_ref())
+            .split(chunks[1]);
+        let text = self.get_help_text();
+        let help = Paragraph::new(Text::from(text.as_str()))
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(help, right_chunks[1]);
+        if self.pane == Pane::Alarms {
+            self.draw_alarms(f, chunks[0]);
+            self.draw_alarm_detail(f, right_chunks[0]);
+            return;
+        }
+        let (fetching, error, truncated) = match self.metrics_state.try_lock() {
+            Ok(m_guard) => {
+                let level = self.menu.get_level().clone();
+                let is_level = m_guard.metrics_filter == level.get_filter();
+                if is_level && !self.menu.is_same_items(&m_guard.metrics) {
+                    self.menu.set_items(m_guard.metrics.clone());
+                }
+                let truncated = is_level && m_guard.metrics_next_token.is_some();
+                // the truncated namespaces aren't cached to keep fetching the rest
+                let is_complete = is_level
+                    && !m_guard.metrics_fetching
+                    && m_guard.metrics_error.is_none()
+                    && !truncated;
+                if level == MetricLevel::Namespace && is_complete && self.namespaces.is_none() {
+                    self.namespaces = Some(m_guard.metrics.clone());
+                }
+                (m_guard.metrics_fetching, m_guard.metrics_error.clone(), truncated)
+            },
+            Err(_) => (true, None, false),
+        };
+        let labels = self.menu.get_labels();
+        let items: Vec<ListItem> = labels.iter()
+            .map(|i| ListItem::new(i.as_ref())).collect();
+        let title = self.get_menu_title(fetching, error, truncated);
+        let menu_block = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(title.as_str())
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        if let Some(ref mut state) = self.menu.get_state() {
+            f.render_stateful_widget(menu_block, chunks[0], state);
+        }
+        self.draw_chart(f, right_chunks[0]);
+    }
+
+    async fn handle_event(&mut self, event: KeyEvent) -> bool {
+        if event.modifiers == KeyModifiers::CONTROL && event.code == KeyCode::Char('a') {
+            self.toggle_pane();
+            return true;
+        }
+        if self.pane == Pane::Alarms {
+            return self.handle_alarms_event(event);
+        }
+        if self.handle_chart_event(event) || self.handle_window_event(event) {
+            return true;
+        }
+        if event.modifiers == KeyModifiers::CONTROL && event.code == KeyCode::Char('e') {
+            self.fetch_more_metrics();
+            return true;
+        }
+        let mut solved = true;
+        match event.code {
+            KeyCode::Down => self.menu.next(),
+            KeyCode::Up => self.menu.previous(),
+            KeyCode::Enter => self.enter(),
+            KeyCode::Esc => solved = self.back(),
+            KeyCode::Backspace => self.pop_char_from_query(),
+            KeyCode::Char(ch) => self.push_char_to_query(ch),
+            _ => solved = false
+        }
+        solved
+    }
+
+    fn take_navigation(&mut self) -> Option<Navigation> {
+        self.navigation.take()
     }
 
-    async fn handle_event(&mut self, _event: KeyEvent) -> bool {
-        false
+    fn reset(&mut self) {
+        self.pane = Pane::Metrics;
+        self.alarms = AlarmList::new(vec![]);
+        self.alarm_prefix = None;
+        self.menu = MetricMenuList::new(vec![], MetricLevel::Namespace);
+        self.query = None;
+        self.chart_metrics = vec![];
+        self.chart_range = (0, 0);
+        self.window = None;
+        self.alarm_period = None;
+        self.fetched_settings = None;
+        self.namespaces = None;
     }
 }
@@ -1,16 +1,47 @@
+use rusoto_cloudwatch::{AlarmHistoryItem, Metric, MetricAlarm};
+use crate::tail::TailSettings;
 use crate::utils::{
     loggroup_menulist::LogGroupMenuList,
     logevent_list::LogEventList,
+    logstream_menulist::LogStreamMenuList,
+    metric_series::MetricSeries,
+    queryresult_table::QueryResultTable,
 };
 
 pub struct GlobalState {
     pub log_groups: LogGroupMenuList,
     pub log_groups_next_token: Option<String>,
     pub log_groups_fething: bool,
+    /// error of the last fetch. the same applies to the other `_error` fields
+    pub log_groups_error: Option<String>,
     pub log_events: LogEventList,
     pub log_events_next_token: Option<String>,
     pub log_events_fetching: bool,
+    /// error of the events of a search or a log stream
+    pub log_events_error: Option<String>,
     pub log_events_selected_log_group_name: String,
+    pub query_id: Option<String>,
+    /// Insights query status such as "Running" or "Complete"
+    pub query_status: Option<String>,
+    pub query_results: QueryResultTable,
+    pub query_fetching: bool,
+    pub query_error: Option<String>,
+    pub log_streams: LogStreamMenuList,
+    /// log group and prefix which `log_streams` are fetched with
+    pub log_streams_filter: Option<(String, Option<String>)>,
+    pub log_streams_fetching: bool,
+    pub log_streams_error: Option<String>,
+    /// a page of the events of a log stream
+    pub stream_events: LogEventList,
+    pub stream_events_forward_token: Option<String>,
+    pub stream_events_backward_token: Option<String>,
+    pub stream_events_fetching: bool,
+    /// events around the event selected for the context
+    pub context_events: LogEventList,
+    /// index of the selected event in `context_events`
+    pub context_highlighted: Option<usize>,
+    pub context_fetching: bool,
+    pub context_error: Option<String>,
 }
 
 impl GlobalState {
@@ -19,10 +50,29 @@ impl GlobalState {
             log_groups: LogGroupMenuList::new(vec![]),
             log_groups_next_token: None,
             log_groups_fething: false,
+            log_groups_error: None,
             log_events: LogEventList::new(vec![]),
             log_events_next_token: None,
             log_events_fetching: false,
+            log_events_error: None,
             log_events_selected_log_group_name: String::from(""),
+            query_id: None,
+            query_status: None,
+            query_results: QueryResultTable::new(),
+            query_fetching: false,
+            query_error: None,
+            log_streams: LogStreamMenuList::new(vec![]),
+            log_streams_filter: None,
+            log_streams_fetching: false,
+            log_streams_error: None,
+            stream_events: LogEventList::new(vec![]),
+            stream_events_forward_token: None,
+            stream_events_backward_token: None,
+            stream_events_fetching: false,
+            context_events: LogEventList::new(vec![]),
+            context_highlighted: None,
+            context_fetching: false,
+            context_error: None,
         }
     }
 
@@ -30,29 +80,112 @@ impl GlobalState {
         self.log_events.clear_items();
         self.log_events_next_token = None;
     }
+
+    pub fn reset_stream_events(&mut self) {
+        self.stream_events.clear_items();
+        self.stream_events_forward_token = None;
+        self.stream_events_backward_token = None;
+    }
+
+    pub fn reset_query_results(&mut self) {
+        self.query_id = None;
+        self.query_status = None;
+        self.query_results.clear_items();
+        self.query_error = None;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
 }
 
 pub struct GlobalStateTail {
     pub log_events: LogEventList,
-    pub log_events_next_token: Option<String>,
+    /// the tail is started over from the next poll
+    pub restart_requested: bool,
     pub log_events_fetching: bool,
-    pub log_events_selected_log_group_name: String,
+    /// errors of the last poll by the log groups
+    pub log_events_error: Option<String>,
+    /// log groups which are tailed together
+    pub log_events_selected_log_group_names: Vec<String>,
     pub log_events_filter_pattern: Option<String>,
+    pub log_events_stream_prefix: Option<String>,
+    /// whether the logs view is in tail mode. nothing is polled otherwise
+    pub active: bool,
+    pub settings: TailSettings,
 }
 
 impl GlobalStateTail {
     pub fn new() -> Self {
         Self {
             log_events: LogEventList::new(vec![]),
-            log_events_next_token: None,
+            restart_requested: false,
             log_events_fetching: false,
-            log_events_selected_log_group_name: String::from(""),
+            log_events_error: None,
+            log_events_selected_log_group_names: vec![],
             log_events_filter_pattern: None,
+            log_events_stream_prefix: None,
+            active: false,
+            settings: TailSettings::default(),
         }
     }
 
     pub fn reset_log_event_results(&mut self) {
         self.log_events.clear_items();
-        self.log_events_next_token = None;
+        self.restart_requested = true;
+    }
+
+    /// the settings are kept
+    pub fn reset(&mut self) {
+        let settings = self.settings;
+        *self = Self::new();
+        self.settings = settings;
+    }
+}
+
+pub struct GlobalStateMetrics {
+    pub metrics: Vec<Metric>,
+    /// namespace and metric name filters which `metrics` are fetched with
+    pub metrics_filter: (Option<String>, Option<String>),
+    pub metrics_fetching: bool,
+    pub metrics_error: Option<String>,
+    /// token of the metrics which aren't fetched yet. Some means `metrics` is truncated
+    pub metrics_next_token: Option<String>,
+    pub metric_data: Vec<MetricSeries>,
+    pub metric_data_fetching: bool,
+    pub metric_data_error: Option<String>,
+    pub alarms: Vec<MetricAlarm>,
+    pub alarms_fetching: bool,
+    pub alarms_error: Option<String>,
+    /// alarm name and its history
+    pub alarm_history: Option<(String, Vec<AlarmHistoryItem>)>,
+}
+
+impl GlobalStateMetrics {
+    pub fn new() -> Self {
+        Self {
+            metrics: vec![],
+            metrics_filter: (None, None),
+            metrics_fetching: false,
+            metrics_error: None,
+            metrics_next_token: None,
+            metric_data: vec![],
+            metric_data_fetching: false,
+            metric_data_error: None,
+            alarms: vec![],
+            alarms_fetching: false,
+            alarms_error: None,
+            alarm_history: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for GlobalStateMetrics {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,1116 @@
+use rusoto_logs::{
+    DescribeLogGroupsRequest,
+    DescribeLogStreamsRequest,
+    FilterLogEventsRequest,
+    FilteredLogEvent,
+    GetLogEventsRequest,
+    GetQueryResultsRequest,
+    OutputLogEvent,
+    StartQueryRequest,
+    StopQueryRequest,
+};
+use rusoto_cloudwatch::{
+    DescribeAlarmHistoryInput,
+    DescribeAlarmsInput,
+    GetMetricDataInput,
+    ListMetricsInput,
+    Metric,
+    MetricDataQuery,
+    MetricStat,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use futures::future::join_all;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::globalstate::{GlobalState, GlobalStateMetrics, GlobalStateTail};
+use crate::instruction::Instruction;
+use crate::logsource::LogSource;
+use crate::metricsource::MetricSource;
+use crate::tail::{MAX_TAIL_EVENTS, TAIL_LOOK_BACK, TailEngine, TailTarget};
+use crate::utils::{
+    logstream_menulist::LogStreamMenuList,
+    metric_series::{MetricSeries, get_series_label},
+};
+
+/// max number of log streams listed in the menu
+const MAX_LOG_STREAMS: usize = 200;
+
+/// number of events in a page of a log stream
+const STREAM_EVENTS_PAGE_SIZE: i64 = 100;
+
+/// max number of the pages of ListMetrics. the namespace level would list every metric of the account
+const MAX_METRIC_PAGES: usize = 10;
+
+/// number of events fetched before and after the event of the context
+const CONTEXT_SIZE: i64 = 10;
+
+/// events of GetLogEvents as the rows of the log events table. `page` makes the event ids unique
+fn to_filtered_events(events: Vec<OutputLogEvent>, log_stream_name: &str, page: &str) -> Vec<FilteredLogEvent> {
+    events
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| FilteredLogEvent {
+            event_id: Some(format!("{}#{}", page, i)),
+            log_stream_name: Some(log_stream_name.to_string()),
+            timestamp: e.timestamp,
+            ingestion_time: e.ingestion_time,
+            message: e.message,
+        })
+        .collect()
+}
+
+/// request for `Instruction::FetchLogEvents`. 0 of start and end means unbounded
+pub fn build_filter_request(
+    log_group_name: String,
+    filter_pattern: String,
+    log_stream_name_prefix: Option<String>,
+    start: i64,
+    end: i64,
+    next_token: Option<String>,
+    limit: i64,
+) -> FilterLogEventsRequest {
+    let start = if start == 0 {
+        None
+    } else {
+        Some(start)
+    };
+    let end = if end == 0 {
+        None
+    } else {
+        Some(end)
+    };
+    FilterLogEventsRequest {
+        log_group_name,
+        filter_pattern: Some(filter_pattern),
+        log_stream_name_prefix: log_stream_name_prefix.filter(|p| !p.is_empty()),
+        next_token,
+        start_time: start,
+        end_time: end,
+        limit: Some(limit),
+        ..Default::default()
+    }
+}
+
+/// executes the instructions sent from the UI and stores the results into the global state
+pub struct InstructionHandler<S: LogSource, M: MetricSource> {
+    source: S,
+    metric_source: M,
+    state: Arc<Mutex<GlobalState>>,
+    metrics_state: Arc<Mutex<GlobalStateMetrics>>,
+}
+
+impl<S: LogSource, M: MetricSource> InstructionHandler<S, M> {
+    pub fn new(
+        source: S,
+        metric_source: M,
+        state: Arc<Mutex<GlobalState>>,
+        metrics_state: Arc<Mutex<GlobalStateMetrics>>,
+    ) -> Self {
+        Self {
+            source,
+            metric_source,
+            state,
+            metrics_state,
+        }
+    }
+
+    pub async fn handle(&self, instruction: Instruction) {
+        match instruction {
+            Instruction::FetchLogEvents(log_group_name, filter_pattern, log_stream_name_prefix, start, end) => {
+                self.fetch_log_events(log_group_name, filter_pattern, log_stream_name_prefix, start, end).await;
+            },
+            Instruction::FetchLogGroups => self.fetch_log_groups().await,
+            Instruction::FetchLogStreams(log_group_name, prefix) => {
+                self.fetch_log_streams(log_group_name, prefix).await;
+            },
+            Instruction::FetchStreamEvents(log_group_name, log_stream_name, token, start, end) => {
+                self.fetch_stream_events(log_group_name, log_stream_name, token, start, end).await;
+            },
+            Instruction::FetchContext(log_group_name, log_stream_name, timestamp, message) => {
+                self.fetch_context(log_group_name, log_stream_name, timestamp, message).await;
+            },
+            Instruction::StartQuery(log_group_names, query, start, end) => {
+                self.start_query(log_group_names, query, start, end).await;
+            },
+            Instruction::FetchQueryResults(query_id) => self.fetch_query_results(query_id).await,
+            Instruction::StopQuery(query_id) => self.stop_query(query_id).await,
+            Instruction::FetchMetrics(namespace, metric_name, token) => {
+                self.fetch_metrics(namespace, metric_name, token).await;
+            },
+            Instruction::FetchMetricData(metrics, statistic, period, start, end) => {
+                self.fetch_metric_data(metrics, statistic, period, start, end).await;
+            },
+            Instruction::FetchAlarms => self.fetch_alarms().await,
+            Instruction::FetchAlarmHistory(alarm_name) => self.fetch_alarm_history(alarm_name).await,
+        }
+    }
+
+    async fn fetch_log_events(
+        &self,
+        log_group_name: String,
+        filter_pattern: String,
+        log_stream_name_prefix: Option<String>,
+        start: i64,
+        end: i64,
+    ) {
+        self.state.lock().unwrap().log_events_fetching = true;
+        if log_group_name != self.state.lock().unwrap().log_events_selected_log_group_name {
+            self.state.lock().unwrap().log_events.clear_items();
+        }
+        let next_token = self.state.lock().unwrap().log_events_next_token.clone();
+        let request = build_filter_request(
+            log_group_name.clone(),
+            filter_pattern,
+            log_stream_name_prefix,
+            start,
+            end,
+            next_token,
+            10,
+        );
+        let response = self.source.filter_log_events(request).await;
+        let mut state = self.state.lock().unwrap();
+        match response {
+            Ok(res) => {
+                state.log_events_selected_log_group_name = log_group_name;
+                state.log_events_next_token = res.next_token.clone();
+                let mut events = res.events.unwrap_or_default();
+                let token = state.log_events_next_token.clone();
+                state.log_events.push_items(&mut events, token.as_ref());
+                state.log_events_error = None;
+            },
+            Err(e) => state.log_events_error = Some(e.to_string()),
+        }
+        state.log_events_fetching = false;
+    }
+
+    /// fetch the log streams, the recently written first.
+    /// DescribeLogStreams can't order by the last event time with a prefix,
+    /// so the streams of the prefix are fetched by name and sorted here
+    async fn fetch_log_streams(&self, log_group_name: String, prefix: Option<String>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.log_streams_fetching = true;
+            state.log_streams_filter = Some((log_group_name.clone(), prefix.clone()));
+        }
+        let order_by = if prefix.is_some() { "LogStreamName" } else { "LastEventTime" };
+        let mut log_streams = vec![];
+        let mut error = None;
+        let mut next_token = None;
+        loop {
+            let request = DescribeLogStreamsRequest {
+                log_group_name: log_group_name.clone(),
+                log_stream_name_prefix: prefix.clone(),
+                order_by: Some(order_by.to_string()),
+                descending: Some(prefix.is_none()),
+                limit: Some(50),
+                next_token,
+            };
+            let response = match self.source.describe_log_streams(request).await {
+                Ok(res) => res,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                },
+            };
+            log_streams.append(&mut response.log_streams.unwrap_or_default());
+            next_token = response.next_token;
+            if next_token.is_none() || log_streams.len() >= MAX_LOG_STREAMS {
+                break;
+            }
+        }
+        if prefix.is_some() {
+            log_streams.sort_by_key(|s| std::cmp::Reverse(s.last_event_timestamp));
+        }
+        let mut state = self.state.lock().unwrap();
+        // ignore the result if another group or prefix is requested meanwhile
+        if state.log_streams_filter == Some((log_group_name, prefix)) {
+            state.log_streams = LogStreamMenuList::new(log_streams);
+            state.log_streams_error = error;
+        }
+        state.log_streams_fetching = false;
+    }
+
+    /// fetch a page of the events of the log stream. the page is kept if the token has no more events
+    async fn fetch_stream_events(
+        &self,
+        log_group_name: String,
+        log_stream_name: String,
+        token: Option<String>,
+        start: i64,
+        end: i64,
+    ) {
+        self.state.lock().unwrap().stream_events_fetching = true;
+        let request = GetLogEventsRequest {
+            log_group_name,
+            log_stream_name: log_stream_name.clone(),
+            next_token: token.clone(),
+            start_time: if start == 0 { None } else { Some(start) },
+            end_time: if end == 0 { None } else { Some(end) },
+            limit: Some(STREAM_EVENTS_PAGE_SIZE),
+            start_from_head: Some(false),
+        };
+        let response = self.source.get_log_events(request).await;
+        let mut state = self.state.lock().unwrap();
+        match response {
+            Ok(res) => {
+                let page = token.clone().unwrap_or_else(|| String::from("latest"));
+                let events = to_filtered_events(res.events.unwrap_or_default(), &log_stream_name, &page);
+                if !events.is_empty() || token.is_none() {
+                    state.stream_events.clear_items();
+                    state.stream_events.set_items(events);
+                    state.stream_events_forward_token = res.next_forward_token;
+                    state.stream_events_backward_token = res.next_backward_token;
+                }
+                state.log_events_error = None;
+            },
+            Err(e) => state.log_events_error = Some(e.to_string()),
+        }
+        state.stream_events_fetching = false;
+    }
+
+    /// fetch the events before and after the event in its log stream.
+    /// the events at the same timestamp are fetched with the later ones so that the event itself is included
+    async fn fetch_context(&self, log_group_name: String, log_stream_name: String, timestamp: i64, message: String) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.context_fetching = true;
+            state.context_events.clear_items();
+            state.context_highlighted = None;
+            state.context_error = None;
+        }
+        let request = |start_time, end_time, start_from_head, limit| GetLogEventsRequest {
+            log_group_name: log_group_name.clone(),
+            log_stream_name: log_stream_name.clone(),
+            start_time,
+            end_time,
+            start_from_head: Some(start_from_head),
+            limit: Some(limit),
+            next_token: None,
+        };
+        let before = self.source.get_log_events(request(None, Some(timestamp), false, CONTEXT_SIZE)).await;
+        let after = self.source.get_log_events(request(Some(timestamp), None, true, CONTEXT_SIZE + 1)).await;
+        let mut state = self.state.lock().unwrap();
+        match (before, after) {
+            (Ok(before), Ok(after)) => {
+                let mut events = before.events.unwrap_or_default();
+                let after = after.events.unwrap_or_default();
+                let highlighted = after
+                    .iter()
+                    .position(|e| e.timestamp == Some(timestamp) && e.message.as_deref() == Some(message.as_str()))
+                    .or_else(|| after.iter().position(|e| e.timestamp == Some(timestamp)))
+                    .map(|idx| idx + events.len());
+                events.extend(after);
+                state.context_events.set_items(to_filtered_events(events, &log_stream_name, "context"));
+                state.context_highlighted = highlighted;
+            },
+            (Err(e), _) | (_, Err(e)) => state.context_error = Some(e.to_string()),
+        }
+        state.context_fetching = false;
+    }
+
+    /// fetch all the pages of the log groups. the pages fetched before an error are kept
+    async fn fetch_log_groups(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.log_groups_fething = true;
+            state.log_groups_error = None;
+        }
+        loop {
+            let request = DescribeLogGroupsRequest {
+                limit: Some(50),
+                log_group_name_prefix: None,
+                next_token: self.state.lock().unwrap().log_groups_next_token.clone(),
+            };
+            let response = self.source.describe_log_groups(request).await;
+            let mut state = self.state.lock().unwrap();
+            match response {
+                Ok(res) => {
+                    state.log_groups_next_token = res.next_token;
+                    let mut log_groups = res.log_groups.unwrap_or_default();
+                    let token = state.log_groups_next_token.clone();
+                    state.log_groups.push_items(&mut log_groups, token.as_ref());
+                    if token.is_none() {
+                        break;
+                    }
+                },
+                Err(e) => {
+                    state.log_groups_error = Some(e.to_string());
+                    break;
+                },
+            }
+        }
+        self.state.lock().unwrap().log_groups_fething = false;
+    }
+
+    async fn start_query(&self, log_group_names: Vec<String>, query: String, start: i64, end: i64) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.reset_query_results();
+            state.query_status = Some(String::from("Scheduled"));
+        }
+        let end = if end == 0 {
+            now_millis()
+        } else {
+            end
+        };
+        // Insights takes seconds
+        let request = StartQueryRequest {
+            log_group_names: Some(log_group_names),
+            query_string: query,
+            start_time: start / 1000,
+            end_time: end / 1000,
+            ..Default::default()
+        };
+        let response = self.source.start_query(request).await;
+        let mut state = self.state.lock().unwrap();
+        match response {
+            Ok(res) => {
+                state.query_id = res.query_id;
+                state.query_status = Some(String::from("Running"));
+            },
+            Err(e) => {
+                state.query_status = Some(String::from("Failed"));
+                state.query_error = Some(e.to_string());
+            },
+        }
+    }
+
+    async fn fetch_query_results(&self, query_id: String) {
+        self.state.lock().unwrap().query_fetching = true;
+        let request = GetQueryResultsRequest {
+            query_id: query_id.clone(),
+        };
+        let response = self.source.get_query_results(request).await;
+        let mut state = self.state.lock().unwrap();
+        state.query_fetching = false;
+        // the query may have been replaced while fetching
+        if state.query_id.as_ref() != Some(&query_id) || !is_query_running(&state.query_status) {
+            return;
+        }
+        match response {
+            Ok(res) => {
+                state.query_results.set_results(&res.results.unwrap_or_default());
+                state.query_status = res.status;
+            },
+            Err(e) => {
+                state.query_status = Some(String::from("Failed"));
+                state.query_error = Some(e.to_string());
+            },
+        }
+    }
+
+    async fn stop_query(&self, query_id: String) {
+        let response = self.source.stop_query(StopQueryRequest { query_id: query_id.clone() }).await;
+        let mut state = self.state.lock().unwrap();
+        if state.query_id.as_ref() != Some(&query_id) {
+            return;
+        }
+        match response {
+            Ok(_) => state.query_status = Some(String::from("Cancelled")),
+            Err(e) => state.query_error = Some(e.to_string()),
+        }
+    }
+
+    /// fetch the metrics of the level. only a metric per namespace is kept for the namespace level.
+    /// at most `MAX_METRIC_PAGES` pages are fetched at once, and the rest is fetched from the kept token.
+    /// the metrics are appended to the fetched ones if `token` is given
+    async fn fetch_metrics(&self, namespace: Option<String>, metric_name: Option<String>, token: Option<String>) {
+        {
+            let mut state = self.metrics_state.lock().unwrap();
+            state.metrics_fetching = true;
+            if token.is_none() {
+                state.metrics = vec![];
+            }
+            state.metrics_filter = (namespace.clone(), metric_name.clone());
+            state.metrics_error = None;
+            state.metrics_next_token = None;
+        }
+        let mut next_token = token;
+        for _ in 0..MAX_METRIC_PAGES {
+            let request = ListMetricsInput {
+                namespace: namespace.clone(),
+                metric_name: metric_name.clone(),
+                next_token,
+                ..Default::default()
+            };
+            let response = match self.metric_source.list_metrics(request).await {
+                Ok(res) => res,
+                Err(e) => {
+                    self.metrics_state.lock().unwrap().metrics_error = Some(e.to_string());
+                    break;
+                },
+            };
+            let mut state = self.metrics_state.lock().unwrap();
+            for metric in response.metrics.unwrap_or_default() {
+                if namespace.is_some() || !state.metrics.iter().any(|m| m.namespace == metric.namespace) {
+                    state.metrics.push(metric);
+                }
+            }
+            next_token = response.next_token;
+            state.metrics_next_token = next_token.clone();
+            if next_token.is_none() {
+                break;
+            }
+        }
+        self.metrics_state.lock().unwrap().metrics_fetching = false;
+    }
+
+    async fn fetch_metric_data(&self, metrics: Vec<Metric>, statistic: String, period: i64, start: i64, end: i64) {
+        self.metrics_state.lock().unwrap().metric_data_fetching = true;
+        let queries: Vec<MetricDataQuery> = metrics
+            .iter()
+            .enumerate()
+            .map(|(i, metric)| MetricDataQuery {
+                id: format!("m{}", i),
+                label: Some(get_series_label(metric)),
+                metric_stat: Some(MetricStat {
+                    metric: metric.clone(),
+                    period,
+                    stat: statistic.clone(),
+                    unit: None,
+                }),
+                return_data: Some(true),
+                ..Default::default()
+            })
+            .collect();
+        let mut series: Vec<MetricSeries> = metrics
+            .iter()
+            .map(|metric| MetricSeries::new(get_series_label(metric)))
+            .collect();
+        let mut error = None;
+        let mut next_token = None;
+        loop {
+            let request = GetMetricDataInput {
+                start_time: Utc.timestamp_millis(start).to_rfc3339(),
+                end_time: Utc.timestamp_millis(end).to_rfc3339(),
+                metric_data_queries: queries.clone(),
+                next_token,
+                scan_by: Some(String::from("TimestampAscending")),
+                ..Default::default()
+            };
+            let response = match self.metric_source.get_metric_data(request).await {
+                Ok(res) => res,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                },
+            };
+            for result in response.metric_data_results.unwrap_or_default() {
+                let idx = result.id
+                    .as_ref()
+                    .and_then(|id| id.trim_start_matches('m').parse::<usize>().ok());
+                if let Some(s) = idx.and_then(|idx| series.get_mut(idx)) {
+                    let timestamps = result.timestamps.unwrap_or_default();
+                    let values = result.values.unwrap_or_default();
+                    for (timestamp, value) in timestamps.iter().zip(values.iter()) {
+                        if let Ok(timestamp) = DateTime::parse_from_rfc3339(timestamp) {
+                            s.points.push((timestamp.timestamp_millis() as f64, *value));
+                        }
+                    }
+                }
+            }
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        for s in series.iter_mut() {
+            s.points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        let mut state = self.metrics_state.lock().unwrap();
+        state.metric_data = series;
+        state.metric_data_error = error;
+        state.metric_data_fetching = false;
+    }
+
+    async fn fetch_alarms(&self) {
+        self.metrics_state.lock().unwrap().alarms_fetching = true;
+        let mut alarms = vec![];
+        let mut error = None;
+        let mut next_token = None;
+        loop {
+            let request = DescribeAlarmsInput {
+                next_token,
+                ..Default::default()
+            };
+            let response = match self.metric_source.describe_alarms(request).await {
+                Ok(res) => res,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                },
+            };
+            alarms.append(&mut response.metric_alarms.unwrap_or_default());
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        let mut state = self.metrics_state.lock().unwrap();
+        state.alarms = alarms;
+        state.alarms_error = error;
+        state.alarms_fetching = false;
+    }
+
+    async fn fetch_alarm_history(&self, alarm_name: String) {
+        let request = DescribeAlarmHistoryInput {
+            alarm_name: Some(alarm_name.clone()),
+            max_records: Some(50),
+            ..Default::default()
+        };
+        let response = self.metric_source.describe_alarm_history(request).await;
+        let mut state = self.metrics_state.lock().unwrap();
+        match response {
+            Ok(res) => {
+                let items = res.alarm_history_items.unwrap_or_default();
+                state.alarm_history = Some((alarm_name, items));
+            },
+            Err(e) => state.alarms_error = Some(e.to_string()),
+        }
+    }
+}
+
+/// whether GetQueryResults should be polled for the status
+pub fn is_query_running(status: &Option<String>) -> bool {
+    matches!(status.as_deref(), Some("Scheduled") | Some("Running"))
+}
+
+/// current unix time in milliseconds
+pub fn now_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+}
+
+/// polls the new log events of the selected log groups for tail mode.
+/// every log group has its own engine, and they're polled concurrently
+pub struct TailHandler<S: LogSource + Clone> {
+    source: S,
+    state: Arc<Mutex<GlobalStateTail>>,
+    engines: Vec<TailEngine<S>>,
+    last_poll: Option<Instant>,
+}
+
+impl<S: LogSource + Clone> TailHandler<S> {
+    pub fn new(source: S, state: Arc<Mutex<GlobalStateTail>>) -> Self {
+        Self {
+            source,
+            state,
+            engines: vec![],
+            last_poll: None,
+        }
+    }
+
+    fn get_targets(state: &GlobalStateTail) -> Vec<TailTarget> {
+        state.log_events_selected_log_group_names
+            .iter()
+            .map(|log_group_name| TailTarget {
+                log_group_name: log_group_name.clone(),
+                filter_pattern: state.log_events_filter_pattern.clone(),
+                log_stream_name_prefix: state.log_events_stream_prefix.clone(),
+            })
+            .collect()
+    }
+
+    fn is_polling(&self, targets: &[TailTarget]) -> bool {
+        self.engines.iter().map(|e| e.get_target()).eq(targets.iter())
+    }
+
+    /// whether the interval has passed since the last poll. the tail is polled at once when it's started over
+    pub fn is_due(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        if !state.active {
+            return false;
+        }
+        if state.restart_requested || !self.is_polling(&Self::get_targets(&state)) {
+            return true;
+        }
+        match self.last_poll {
+            Some(last_poll) => last_poll.elapsed() >= state.settings.interval,
+            None => true,
+        }
+    }
+
+    /// the tail is started over when the targets are changed or the results are cleared
+    pub async fn poll(&mut self) {
+        self.last_poll = Some(Instant::now());
+        {
+            let mut state = self.state.lock().unwrap();
+            let targets = Self::get_targets(&state);
+            if state.restart_requested || !self.is_polling(&targets) {
+                state.restart_requested = false;
+                state.log_events.clear_items();
+                let source = &self.source;
+                self.engines = targets
+                    .into_iter()
+                    .map(|target| TailEngine::new(source.clone(), target, TAIL_LOOK_BACK))
+                    .collect();
+            }
+        }
+        if self.engines.is_empty() {
+            return;
+        }
+        {
+            let mut state = self.state.lock().unwrap();
+            state.log_events_fetching = true;
+            for engine in self.engines.iter_mut() {
+                engine.set_look_back(state.settings.look_back);
+            }
+        }
+        let now = now_millis();
+        let results = join_all(self.engines.iter_mut().map(|engine| engine.poll(now))).await;
+        let mut state = self.state.lock().unwrap();
+        // drop the events of the old targets which are changed while polling
+        let targets = Self::get_targets(&state);
+        if !state.restart_requested && self.is_polling(&targets) {
+            let is_merged = self.engines.len() > 1;
+            let mut errors = vec![];
+            for (engine, result) in self.engines.iter().zip(results) {
+                let log_group_name = engine.get_target().log_group_name.as_str();
+                match result {
+                    Ok(events) => {
+                        state.log_events.insert_items(if is_merged { Some(log_group_name) } else { None }, events);
+                    },
+                    Err(e) if is_merged => errors.push(format!("{}: {}", log_group_name, e)),
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+            state.log_events.truncate_front(MAX_TAIL_EVENTS);
+            state.log_events_error = if errors.is_empty() { None } else { Some(errors.join(", ")) };
+        }
+        state.log_events_fetching = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logsource::memory::InMemoryLogSource;
+    use crate::metricsource::memory::{InMemoryMetricSource, make_metric};
+    use rusoto_cloudwatch::MetricAlarm;
+    use crate::utils::{StatefulList, StatefulTable};
+    use std::time::Duration;
+
+    fn make_source() -> InMemoryLogSource {
+        let source = InMemoryLogSource::new(2);
+        for i in 0..5 {
+            source.add_log_group(&format!("group{}", i));
+        }
+        for i in 0..3 {
+            source.add_log_event("group0", "stream", 1000 + i, &format!("message{}", i));
+        }
+        source.add_log_event("group1", "stream", 1000, "another");
+        source
+    }
+
+    fn make_metrics_state() -> Arc<Mutex<GlobalStateMetrics>> {
+        Arc::new(Mutex::new(GlobalStateMetrics::new()))
+    }
+
+    fn make_handler(
+        source: InMemoryLogSource,
+        state: &Arc<Mutex<GlobalState>>,
+    ) -> InstructionHandler<InMemoryLogSource, InMemoryMetricSource> {
+        InstructionHandler::new(source, InMemoryMetricSource::new(10), Arc::clone(state), make_metrics_state())
+    }
+
+    #[tokio::test]
+    async fn can_fetch_all_log_groups() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = make_handler(make_source(), &state);
+        handler.handle(Instruction::FetchLogGroups).await;
+        let state = state.lock().unwrap();
+        assert_eq!(5, state.log_groups.get_labels().len());
+        assert!(!state.log_groups.has_more_items());
+        assert!(!state.log_groups_fething);
+    }
+
+    #[tokio::test]
+    async fn errors_are_reported_and_end_fetching() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = make_source();
+        source.set_error(Some("ThrottlingException: Rate exceeded"));
+        let handler = make_handler(source.clone(), &state);
+        handler.handle(Instruction::FetchLogGroups).await;
+        handler.handle(Instruction::FetchLogEvents(String::from("group0"), String::from(""), None, 0, 0)).await;
+        {
+            let state = state.lock().unwrap();
+            assert!(!state.log_groups_fething);
+            assert_eq!(Some(String::from("ThrottlingException: Rate exceeded")), state.log_groups_error);
+            assert!(!state.log_events_fetching);
+            assert_eq!(Some(String::from("ThrottlingException: Rate exceeded")), state.log_events_error);
+        }
+        source.set_error(None);
+        handler.handle(Instruction::FetchLogGroups).await;
+        handler.handle(Instruction::FetchLogEvents(String::from("group0"), String::from(""), None, 0, 0)).await;
+        let state = state.lock().unwrap();
+        assert_eq!(5, state.log_groups.get_labels().len());
+        assert_eq!(None, state.log_groups_error);
+        assert_eq!(None, state.log_events_error);
+    }
+
+    #[tokio::test]
+    async fn can_fetch_log_events_page_by_page() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = make_source();
+        let handler = make_handler(source.clone(), &state);
+        let instruction = || Instruction::FetchLogEvents(String::from("group0"), String::from(""), None, 0, 0);
+        handler.handle(instruction()).await;
+        {
+            let state = state.lock().unwrap();
+            // 2 events and "More..."
+            assert_eq!(3, state.log_events.get_labels().len());
+            assert!(state.log_events.is_last_more_item());
+            assert_eq!(Some(String::from("2")), state.log_events_next_token);
+            assert!(!state.log_events_fetching);
+        }
+        handler.handle(instruction()).await;
+        let state = state.lock().unwrap();
+        assert_eq!(3, state.log_events.get_labels().len());
+        assert!(!state.log_events.is_last_more_item());
+        assert_eq!(None, state.log_events_next_token);
+        let requests = source.get_filter_requests();
+        assert_eq!(None, requests[0].start_time);
+        assert_eq!(Some(String::from("2")), requests[1].next_token);
+    }
+
+    #[tokio::test]
+    async fn can_fetch_log_streams_with_prefix() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = make_source();
+        source.add_log_event("group0", "other", 500, "old");
+        let handler = make_handler(source.clone(), &state);
+        handler.handle(Instruction::FetchLogStreams(String::from("group0"), None)).await;
+        {
+            let state = state.lock().unwrap();
+            assert_eq!(vec![String::from("stream"), String::from("other")], state.log_streams.get_labels());
+            assert!(!state.log_streams_fetching);
+        }
+        handler.handle(Instruction::FetchLogStreams(String::from("group0"), Some(String::from("o")))).await;
+        {
+            let state = state.lock().unwrap();
+            assert_eq!(vec![String::from("other")], state.log_streams.get_labels());
+            assert_eq!(Some((String::from("group0"), Some(String::from("o")))), state.log_streams_filter);
+        }
+        // the streams of the prefix are also the recently written first
+        source.add_log_event("group0", "s-a", 100, "older");
+        source.add_log_event("group0", "s-b", 200, "newer");
+        handler.handle(Instruction::FetchLogStreams(String::from("group0"), Some(String::from("s-")))).await;
+        let state = state.lock().unwrap();
+        assert_eq!(vec![String::from("s-b"), String::from("s-a")], state.log_streams.get_labels());
+    }
+
+    #[tokio::test]
+    async fn can_page_stream_events_backward_and_forward() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = make_handler(make_source(), &state);
+        let instruction = |token: Option<String>| {
+            Instruction::FetchStreamEvents(String::from("group0"), String::from("stream"), token, 0, 0)
+        };
+        let messages = |state: &Arc<Mutex<GlobalState>>| {
+            let state = state.lock().unwrap();
+            (0..2).filter_map(|i| state.stream_events.get_log_event_text(i)).collect::<Vec<String>>()
+        };
+        handler.handle(instruction(None)).await;
+        assert_eq!(vec![String::from("message1"), String::from("message2")], messages(&state));
+        let backward = state.lock().unwrap().stream_events_backward_token.clone();
+        handler.handle(instruction(backward)).await;
+        assert_eq!(vec![String::from("message0")], messages(&state));
+        // no more older events, so the page is kept
+        let backward = state.lock().unwrap().stream_events_backward_token.clone();
+        handler.handle(instruction(backward)).await;
+        assert_eq!(vec![String::from("message0")], messages(&state));
+        let forward = state.lock().unwrap().stream_events_forward_token.clone();
+        handler.handle(instruction(forward)).await;
+        assert_eq!(vec![String::from("message1"), String::from("message2")], messages(&state));
+        assert!(!state.lock().unwrap().stream_events_fetching);
+    }
+
+    #[tokio::test]
+    async fn can_fetch_context_of_event() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = InMemoryLogSource::new(50);
+        source.add_log_group("group0");
+        for i in 0..30 {
+            if i == 15 {
+                source.add_log_event("group0", "stream", 1015, "same timestamp");
+            }
+            source.add_log_event("group0", "stream", 1000 + i, &format!("message{}", i));
+            source.add_log_event("group0", "other", 1000 + i, "other stream");
+        }
+        let handler = make_handler(source, &state);
+        handler.handle(Instruction::FetchContext(
+            String::from("group0"),
+            String::from("stream"),
+            1015,
+            String::from("message15"),
+        )).await;
+        let state = state.lock().unwrap();
+        let messages: Vec<String> = state.context_events.get_labels().iter().map(|l| l[1].clone()).collect();
+        assert_eq!(21, messages.len());
+        assert_eq!("message5", messages[0]);
+        assert_eq!("same timestamp", messages[10]);
+        assert_eq!("message24", messages[20]);
+        assert_eq!(Some(11), state.context_highlighted);
+        assert!(!state.context_fetching);
+    }
+
+    #[tokio::test]
+    async fn log_events_are_cleared_when_log_group_changes() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = make_handler(make_source(), &state);
+        handler.handle(Instruction::FetchLogEvents(String::from("group0"), String::from(""), None, 0, 0)).await;
+        state.lock().unwrap().log_events_next_token = None;
+        handler.handle(Instruction::FetchLogEvents(String::from("group1"), String::from(""), None, 0, 0)).await;
+        let state = state.lock().unwrap();
+        assert_eq!(vec![String::from("another")], state.log_events.get_labels().iter().map(|l| l[1].clone()).collect::<Vec<String>>());
+        assert_eq!("group1", state.log_events_selected_log_group_name);
+    }
+
+    #[tokio::test]
+    async fn can_pass_filter_and_range() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = make_source();
+        let handler = make_handler(source.clone(), &state);
+        handler.handle(Instruction::FetchLogEvents(String::from("group0"), String::from("message2"), None, 1001, 2000)).await;
+        let requests = source.get_filter_requests();
+        assert_eq!(Some(1001), requests[0].start_time);
+        assert_eq!(Some(2000), requests[0].end_time);
+        assert_eq!(None, requests[0].log_stream_name_prefix);
+        assert_eq!(1, state.lock().unwrap().log_events.get_labels().len());
+        handler.handle(Instruction::FetchLogEvents(String::from("group0"), String::from(""), Some(String::from("stream1")), 0, 0)).await;
+        assert_eq!(Some(String::from("stream1")), source.get_filter_requests()[1].log_stream_name_prefix);
+    }
+
+    #[tokio::test]
+    async fn can_run_query_until_complete() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = make_source();
+        source.set_query_results(vec![vec![("count(*)", "3")]]);
+        source.set_query_running_polls(1);
+        let handler = make_handler(source.clone(), &state);
+        let groups = vec![String::from("group0"), String::from("group1")];
+        handler.handle(Instruction::StartQuery(groups.clone(), String::from("stats count(*)"), 60_000, 120_000)).await;
+        let query_id = state.lock().unwrap().query_id.clone().unwrap();
+        assert_eq!(Some(String::from("Running")), state.lock().unwrap().query_status);
+        handler.handle(Instruction::FetchQueryResults(query_id.clone())).await;
+        assert_eq!(Some(String::from("Running")), state.lock().unwrap().query_status);
+        handler.handle(Instruction::FetchQueryResults(query_id)).await;
+        let state = state.lock().unwrap();
+        assert_eq!(Some(String::from("Complete")), state.query_status);
+        assert_eq!(&vec![String::from("count(*)")], state.query_results.get_fields());
+        assert_eq!(vec![vec![String::from("3")]], state.query_results.get_labels());
+        let request = &source.get_query_requests()[0];
+        assert_eq!(Some(groups), request.log_group_names);
+        assert_eq!((60, 120), (request.start_time, request.end_time));
+    }
+
+    #[tokio::test]
+    async fn can_stop_query() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let source = make_source();
+        source.set_query_running_polls(10);
+        let handler = make_handler(source, &state);
+        handler.handle(Instruction::StartQuery(vec![String::from("group0")], String::from("fields @message"), 0, 0)).await;
+        let query_id = state.lock().unwrap().query_id.clone().unwrap();
+        handler.handle(Instruction::StopQuery(query_id.clone())).await;
+        assert_eq!(Some(String::from("Cancelled")), state.lock().unwrap().query_status);
+        handler.handle(Instruction::FetchQueryResults(query_id)).await;
+        assert_eq!(Some(String::from("Cancelled")), state.lock().unwrap().query_status);
+    }
+
+    #[tokio::test]
+    async fn query_fails_with_unknown_log_group() {
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = make_handler(make_source(), &state);
+        handler.handle(Instruction::StartQuery(vec![String::from("unknown")], String::from("fields @message"), 0, 0)).await;
+        let state = state.lock().unwrap();
+        assert_eq!(Some(String::from("Failed")), state.query_status);
+        assert!(state.query_error.as_ref().unwrap().contains("ResourceNotFoundException"));
+    }
+
+    #[tokio::test]
+    async fn can_fetch_metrics_of_level() {
+        let metric_source = InMemoryMetricSource::new(1);
+        metric_source.add_metric("AWS/Lambda", "Errors", &[("FunctionName", "f1")]);
+        metric_source.add_metric("AWS/Lambda", "Errors", &[("FunctionName", "f2")]);
+        metric_source.add_metric("AWS/EC2", "CPUUtilization", &[]);
+        let metrics_state = make_metrics_state();
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = InstructionHandler::new(make_source(), metric_source.clone(), state, Arc::clone(&metrics_state));
+        handler.handle(Instruction::FetchMetrics(None, None, None)).await;
+        assert_eq!(2, metrics_state.lock().unwrap().metrics.len());
+        handler.handle(Instruction::FetchMetrics(Some(String::from("AWS/Lambda")), Some(String::from("Errors")), None)).await;
+        let metrics_state = metrics_state.lock().unwrap();
+        assert_eq!(2, metrics_state.metrics.len());
+        assert_eq!((Some(String::from("AWS/Lambda")), Some(String::from("Errors"))), metrics_state.metrics_filter);
+        assert!(!metrics_state.metrics_fetching);
+        assert_eq!(Some(String::from("1")), metric_source.get_list_requests()[4].next_token);
+    }
+
+    #[tokio::test]
+    async fn metrics_are_fetched_from_kept_token_after_max_pages() {
+        let metric_source = InMemoryMetricSource::new(1);
+        for i in 0..12 {
+            metric_source.add_metric(&format!("Custom/{:02}", i), "Errors", &[]);
+        }
+        let metrics_state = make_metrics_state();
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = InstructionHandler::new(make_source(), metric_source.clone(), state, Arc::clone(&metrics_state));
+        handler.handle(Instruction::FetchMetrics(None, None, None)).await;
+        let token = {
+            let metrics_state = metrics_state.lock().unwrap();
+            assert_eq!(MAX_METRIC_PAGES, metrics_state.metrics.len());
+            assert_eq!(Some(String::from("10")), metrics_state.metrics_next_token);
+            assert!(!metrics_state.metrics_fetching);
+            metrics_state.metrics_next_token.clone()
+        };
+        assert_eq!(MAX_METRIC_PAGES, metric_source.get_list_requests().len());
+        handler.handle(Instruction::FetchMetrics(None, None, token)).await;
+        let metrics_state = metrics_state.lock().unwrap();
+        assert_eq!(12, metrics_state.metrics.len());
+        assert_eq!(Some(String::from("Custom/11")), metrics_state.metrics[11].namespace);
+        assert_eq!(None, metrics_state.metrics_next_token);
+    }
+
+    #[tokio::test]
+    async fn can_fetch_metric_data_of_several_metrics() {
+        let metric_source = InMemoryMetricSource::new(10);
+        let errors = make_metric("AWS/Lambda", "Errors", &[("FunctionName", "f1")]);
+        let invocations = make_metric("AWS/Lambda", "Invocations", &[("FunctionName", "f1")]);
+        metric_source.add_datapoint(&errors, 120_000, 1.0);
+        metric_source.add_datapoint(&errors, 60_000, 2.0);
+        metric_source.add_datapoint(&invocations, 60_000, 10.0);
+        metric_source.add_datapoint(&invocations, 600_000, 20.0);
+        let metrics_state = make_metrics_state();
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = InstructionHandler::new(make_source(), metric_source.clone(), state, Arc::clone(&metrics_state));
+        let metrics = vec![errors, invocations];
+        handler.handle(Instruction::FetchMetricData(metrics, String::from("Sum"), 60, 0, 300_000)).await;
+        let metrics_state = metrics_state.lock().unwrap();
+        assert_eq!(2, metrics_state.metric_data.len());
+        assert_eq!("Errors FunctionName=f1", metrics_state.metric_data[0].label);
+        assert_eq!(vec![(60_000.0, 2.0), (120_000.0, 1.0)], metrics_state.metric_data[0].points);
+        assert_eq!(vec![(60_000.0, 10.0)], metrics_state.metric_data[1].points);
+        assert!(!metrics_state.metric_data_fetching);
+        let request = &metric_source.get_data_requests()[0];
+        assert_eq!("1970-01-01T00:05:00+00:00", request.end_time);
+        let stat = request.metric_data_queries[1].metric_stat.as_ref().unwrap();
+        assert_eq!(("Sum", 60), (stat.stat.as_str(), stat.period));
+    }
+
+    #[tokio::test]
+    async fn can_fetch_alarms_and_history() {
+        let metric_source = InMemoryMetricSource::new(1);
+        for name in &["a1", "a2"] {
+            metric_source.add_alarm(MetricAlarm {
+                alarm_name: Some(name.to_string()),
+                state_value: Some(String::from("OK")),
+                ..Default::default()
+            });
+        }
+        metric_source.add_alarm_history("a2", "2020-01-01T00:00:00.000Z", "Alarm updated from OK to ALARM");
+        let metrics_state = make_metrics_state();
+        let state = Arc::new(Mutex::new(GlobalState::new()));
+        let handler = InstructionHandler::new(make_source(), metric_source, state, Arc::clone(&metrics_state));
+        handler.handle(Instruction::FetchAlarms).await;
+        handler.handle(Instruction::FetchAlarmHistory(String::from("a2"))).await;
+        let metrics_state = metrics_state.lock().unwrap();
+        assert_eq!(2, metrics_state.alarms.len());
+        assert!(!metrics_state.alarms_fetching);
+        let (alarm_name, history) = metrics_state.alarm_history.as_ref().unwrap();
+        assert_eq!("a2", alarm_name);
+        assert_eq!(Some(String::from("Alarm updated from OK to ALARM")), history[0].history_summary);
+    }
+
+    #[tokio::test]
+    async fn tail_can_poll_latest_events() {
+        let state = Arc::new(Mutex::new(GlobalStateTail::new()));
+        let source = make_source();
+        let now = now_millis();
+        source.add_log_event("group0", "stream", now - 1000, "latest");
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group0")];
+        let mut handler = TailHandler::new(source, Arc::clone(&state));
+        handler.poll().await;
+        handler.poll().await;
+        let state = state.lock().unwrap();
+        assert_eq!(1, state.log_events.get_labels().len());
+        assert_eq!(Some(String::from("latest")), state.log_events.get_log_event_text(0));
+        assert!(!state.log_events_fetching);
+    }
+
+    #[tokio::test]
+    async fn tail_reports_errors_by_log_group() {
+        let state = Arc::new(Mutex::new(GlobalStateTail::new()));
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group0"), String::from("unknown")];
+        let mut handler = TailHandler::new(make_source(), Arc::clone(&state));
+        handler.poll().await;
+        assert_eq!(
+            Some(String::from("unknown: ResourceNotFoundException: unknown")),
+            state.lock().unwrap().log_events_error
+        );
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group0")];
+        handler.poll().await;
+        assert_eq!(None, state.lock().unwrap().log_events_error);
+    }
+
+    #[tokio::test]
+    async fn tail_can_merge_log_groups() {
+        let state = Arc::new(Mutex::new(GlobalStateTail::new()));
+        let source = make_source();
+        let now = now_millis();
+        source.add_log_event("group0", "stream", now - 3000, "group0 first");
+        source.add_log_event("group1", "stream", now - 2000, "group1 second");
+        source.add_log_event("group0", "stream", now - 1000, "group0 third");
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group0"), String::from("group1")];
+        let mut handler = TailHandler::new(source.clone(), Arc::clone(&state));
+        handler.poll().await;
+        source.add_log_event("group1", "stream", now - 500, "group1 fourth");
+        handler.poll().await;
+        let state = state.lock().unwrap();
+        let labels = state.log_events.get_labels();
+        let messages: Vec<&str> = labels.iter().map(|l| l[2].as_str()).collect();
+        assert_eq!(vec!["group0 first", "group1 second", "group0 third", "group1 fourth"], messages);
+        assert_eq!("group1", labels[3][1]);
+    }
+
+    #[tokio::test]
+    async fn tail_is_polled_at_the_interval_while_active() {
+        let state = Arc::new(Mutex::new(GlobalStateTail::new()));
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group0")];
+        state.lock().unwrap().settings.interval = Duration::from_secs(600);
+        let mut handler = TailHandler::new(make_source(), Arc::clone(&state));
+        assert!(!handler.is_due());
+        state.lock().unwrap().active = true;
+        assert!(handler.is_due());
+        handler.poll().await;
+        assert!(!handler.is_due());
+        state.lock().unwrap().log_events_filter_pattern = Some(String::from("ERROR"));
+        assert!(handler.is_due());
+        handler.poll().await;
+        state.lock().unwrap().reset_log_event_results();
+        assert!(handler.is_due());
+        state.lock().unwrap().reset_log_event_results();
+        state.lock().unwrap().reset();
+        assert_eq!(Duration::from_secs(600), state.lock().unwrap().settings.interval);
+    }
+
+    #[tokio::test]
+    async fn tail_starts_over_when_target_changes_or_restarts() {
+        let state = Arc::new(Mutex::new(GlobalStateTail::new()));
+        let source = make_source();
+        let now = now_millis();
+        source.add_log_event("group0", "stream", now - 1000, "group0 latest");
+        source.add_log_event("group1", "stream", now - 1000, "group1 latest");
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group0")];
+        let mut handler = TailHandler::new(source, Arc::clone(&state));
+        handler.poll().await;
+        state.lock().unwrap().log_events_selected_log_group_names = vec![String::from("group1")];
+        handler.poll().await;
+        assert_eq!(Some(String::from("group1 latest")), state.lock().unwrap().log_events.get_log_event_text(0));
+        assert_eq!(1, state.lock().unwrap().log_events.get_labels().len());
+        state.lock().unwrap().reset_log_event_results();
+        handler.poll().await;
+        let state = state.lock().unwrap();
+        assert_eq!(1, state.log_events.get_labels().len());
+        assert!(!state.restart_requested);
+    }
+}
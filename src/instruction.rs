@@ -1,4 +1,23 @@
+use rusoto_cloudwatch::Metric;
+
 pub enum Instruction {
     FetchLogGroups,
-    FetchLogEvents(String, String, i64, i64),
+    FetchLogEvents(String, String, Option<String>, i64, i64),
+    /// log group name, log stream name prefix
+    FetchLogStreams(String, Option<String>),
+    /// log group name, log stream name, token of the page (None means the latest page), start, end
+    FetchStreamEvents(String, String, Option<String>, i64, i64),
+    /// log group name, log stream name, timestamp and message of the event whose surroundings are fetched
+    FetchContext(String, String, i64, String),
+    /// log group names, query string, start and end in epoch millis
+    StartQuery(Vec<String>, String, i64, i64),
+    FetchQueryResults(String),
+    StopQuery(String),
+    /// namespace and metric name filters of ListMetrics, token to continue from (None fetches from the start)
+    FetchMetrics(Option<String>, Option<String>, Option<String>),
+    /// metrics, statistic, period in seconds, start and end in epoch millis
+    FetchMetricData(Vec<Metric>, String, i64, i64, i64),
+    FetchAlarms,
+    /// alarm name
+    FetchAlarmHistory(String),
 }
@@ -4,3 +4,9 @@ pub mod app;
 pub mod utils;
 pub mod globalstate;
 pub mod instruction;
+pub mod aws;
+pub mod logsource;
+pub mod metricsource;
+pub mod handler;
+pub mod tail;
+pub mod cli;
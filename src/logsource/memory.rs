@@ -0,0 +1,500 @@
+use rusoto_logs::{
+    DescribeLogGroupsRequest,
+    DescribeLogGroupsResponse,
+    DescribeLogStreamsRequest,
+    DescribeLogStreamsResponse,
+    FilterLogEventsRequest,
+    FilterLogEventsResponse,
+    FilteredLogEvent,
+    GetLogEventsRequest,
+    GetLogEventsResponse,
+    GetQueryResultsRequest,
+    GetQueryResultsResponse,
+    LogGroup,
+    LogStream,
+    OutputLogEvent,
+    ResultField,
+    StartQueryRequest,
+    StartQueryResponse,
+    StopQueryRequest,
+    StopQueryResponse,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use super::LogSource;
+
+#[derive(Debug, Default)]
+struct Inner {
+    log_groups: Vec<LogGroup>,
+    log_events: Vec<(String, FilteredLogEvent)>,
+    filter_requests: Vec<FilterLogEventsRequest>,
+    next_event_id: usize,
+    query_requests: Vec<StartQueryRequest>,
+    query_results: Vec<Vec<ResultField>>,
+    query_running_polls: usize,
+    /// status and remaining polls until complete of each started query
+    queries: Vec<(String, usize)>,
+    /// error of every request while it's set
+    error: Option<String>,
+}
+
+/// in-memory backend mainly for tests.
+/// clones share the same data, so events can be added while it's used
+#[derive(Debug, Clone)]
+pub struct InMemoryLogSource {
+    inner: Arc<Mutex<Inner>>,
+    page_size: usize,
+}
+
+impl InMemoryLogSource {
+    /// `page_size` is the max number of items in a page
+    pub fn new(page_size: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            page_size,
+        }
+    }
+
+    pub fn add_log_group(&self, log_group_name: &str) {
+        let log_group = LogGroup {
+            arn: Some(format!("arn:aws:logs:memory:000000000000:log-group:{}", log_group_name)),
+            log_group_name: Some(log_group_name.to_string()),
+            ..Default::default()
+        };
+        self.insert_log_group(log_group);
+    }
+
+    /// add a log group with all its attributes
+    pub fn insert_log_group(&self, log_group: LogGroup) {
+        self.inner.lock().unwrap().log_groups.push(log_group);
+    }
+
+    /// add an event. returns the generated event id
+    pub fn add_log_event(&self, log_group_name: &str, log_stream_name: &str, timestamp: i64, message: &str) -> String {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_event_id += 1;
+        let event_id = format!("{:020}", inner.next_event_id);
+        let event = FilteredLogEvent {
+            event_id: Some(event_id.clone()),
+            log_stream_name: Some(log_stream_name.to_string()),
+            timestamp: Some(timestamp),
+            ingestion_time: Some(timestamp),
+            message: Some(message.to_string()),
+        };
+        inner.log_events.push((log_group_name.to_string(), event));
+        inner.log_events.sort_by_key(|(_, e)| (e.timestamp, e.event_id.clone()));
+        event_id
+    }
+
+    /// fail every request with the error (e.g. "ThrottlingException") until it's unset
+    pub fn set_error(&self, error: Option<&str>) {
+        self.inner.lock().unwrap().error = error.map(|e| e.to_string());
+    }
+
+    fn check_error(&self) -> Result<()> {
+        match &self.inner.lock().unwrap().error {
+            Some(error) => Err(anyhow!("{}", error)),
+            None => Ok(()),
+        }
+    }
+
+    /// requests received by `filter_log_events`
+    pub fn get_filter_requests(&self) -> Vec<FilterLogEventsRequest> {
+        self.inner.lock().unwrap().filter_requests.clone()
+    }
+
+    /// results returned by every Insights query
+    pub fn set_query_results(&self, results: Vec<Vec<(&str, &str)>>) {
+        self.inner.lock().unwrap().query_results = results
+            .into_iter()
+            .map(|row| row
+                .into_iter()
+                .map(|(field, value)| ResultField {
+                    field: Some(field.to_string()),
+                    value: Some(value.to_string()),
+                })
+                .collect()
+            )
+            .collect();
+    }
+
+    /// number of `get_query_results` calls answered with "Running" before a query completes
+    pub fn set_query_running_polls(&self, polls: usize) {
+        self.inner.lock().unwrap().query_running_polls = polls;
+    }
+
+    /// requests received by `start_query`
+    pub fn get_query_requests(&self) -> Vec<StartQueryRequest> {
+        self.inner.lock().unwrap().query_requests.clone()
+    }
+
+    fn get_events(&self, log_group_name: &str) -> Vec<FilteredLogEvent> {
+        self.inner.lock().unwrap().log_events
+            .iter()
+            .filter(|(group, _)| group == log_group_name)
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    fn has_log_group(&self, log_group_name: &str) -> bool {
+        self.inner.lock().unwrap().log_groups
+            .iter()
+            .any(|g| g.log_group_name.as_deref() == Some(log_group_name))
+    }
+
+    fn page_limit(&self, limit: Option<i64>) -> usize {
+        match limit {
+            Some(limit) if limit > 0 => (limit as usize).min(self.page_size),
+            _ => self.page_size,
+        }
+    }
+}
+
+fn parse_offset(token: &Option<String>) -> Result<usize> {
+    match token {
+        Some(token) => token.parse().map_err(|_| anyhow!("invalid next token: {}", token)),
+        None => Ok(0),
+    }
+}
+
+/// every term separated by spaces must be contained in the message
+fn matches_pattern(pattern: &Option<String>, message: &Option<String>) -> bool {
+    let message = message.as_deref().unwrap_or("");
+    match pattern {
+        Some(pattern) => pattern.split_whitespace().all(|term| message.contains(term.trim_matches('"'))),
+        None => true,
+    }
+}
+
+fn in_range(timestamp: Option<i64>, start: Option<i64>, end: Option<i64>) -> bool {
+    let timestamp = timestamp.unwrap_or(0);
+    let after_start = match start {
+        Some(start) => timestamp >= start,
+        None => true,
+    };
+    let before_end = match end {
+        Some(end) => timestamp <= end,
+        None => true,
+    };
+    after_start && before_end
+}
+
+#[async_trait]
+impl LogSource for InMemoryLogSource {
+    async fn describe_log_groups(&self, request: DescribeLogGroupsRequest) -> Result<DescribeLogGroupsResponse> {
+        self.check_error()?;
+        let groups: Vec<LogGroup> = self.inner.lock().unwrap().log_groups
+            .iter()
+            .filter(|g| match (&request.log_group_name_prefix, &g.log_group_name) {
+                (Some(prefix), Some(name)) => name.starts_with(prefix.as_str()),
+                _ => true,
+            })
+            .cloned()
+            .collect();
+        let offset = parse_offset(&request.next_token)?;
+        let end = (offset + self.page_limit(request.limit)).min(groups.len());
+        Ok(DescribeLogGroupsResponse {
+            log_groups: Some(groups[offset.min(end)..end].to_vec()),
+            next_token: if end < groups.len() { Some(end.to_string()) } else { None },
+        })
+    }
+
+    async fn describe_log_streams(&self, request: DescribeLogStreamsRequest) -> Result<DescribeLogStreamsResponse> {
+        self.check_error()?;
+        if !self.has_log_group(&request.log_group_name) {
+            return Err(anyhow!("ResourceNotFoundException: {}", request.log_group_name));
+        }
+        let mut streams: Vec<LogStream> = vec![];
+        for event in self.get_events(&request.log_group_name) {
+            let name = event.log_stream_name.clone().unwrap_or_default();
+            if let Some(prefix) = &request.log_stream_name_prefix {
+                if !name.starts_with(prefix.as_str()) {
+                    continue;
+                }
+            }
+            match streams.iter_mut().find(|s| s.log_stream_name.as_deref() == Some(name.as_str())) {
+                Some(stream) => {
+                    stream.last_event_timestamp = event.timestamp;
+                    stream.last_ingestion_time = event.ingestion_time;
+                },
+                None => {
+                    streams.push(LogStream {
+                        log_stream_name: Some(name.clone()),
+                        creation_time: event.timestamp,
+                        first_event_timestamp: event.timestamp,
+                        last_event_timestamp: event.timestamp,
+                        last_ingestion_time: event.ingestion_time,
+                        ..Default::default()
+                    });
+                },
+            }
+        }
+        if request.order_by.as_deref() == Some("LastEventTime") {
+            streams.sort_by_key(|s| s.last_event_timestamp);
+        } else {
+            streams.sort_by_key(|s| s.log_stream_name.clone());
+        }
+        if request.descending == Some(true) {
+            streams.reverse();
+        }
+        let offset = parse_offset(&request.next_token)?;
+        let end = (offset + self.page_limit(request.limit)).min(streams.len());
+        Ok(DescribeLogStreamsResponse {
+            log_streams: Some(streams[offset.min(end)..end].to_vec()),
+            next_token: if end < streams.len() { Some(end.to_string()) } else { None },
+        })
+    }
+
+    async fn filter_log_events(&self, request: FilterLogEventsRequest) -> Result<FilterLogEventsResponse> {
+        self.check_error()?;
+        self.inner.lock().unwrap().filter_requests.push(request.clone());
+        if !self.has_log_group(&request.log_group_name) {
+            return Err(anyhow!("ResourceNotFoundException: {}", request.log_group_name));
+        }
+        let events: Vec<FilteredLogEvent> = self.get_events(&request.log_group_name)
+            .into_iter()
+            .filter(|e| in_range(e.timestamp, request.start_time, request.end_time))
+            .filter(|e| matches_pattern(&request.filter_pattern, &e.message))
+            .filter(|e| {
+                let stream = e.log_stream_name.as_deref().unwrap_or("");
+                let by_names = match &request.log_stream_names {
+                    Some(names) => names.iter().any(|n| n == stream),
+                    None => true,
+                };
+                let by_prefix = match &request.log_stream_name_prefix {
+                    Some(prefix) => stream.starts_with(prefix.as_str()),
+                    None => true,
+                };
+                by_names && by_prefix
+            })
+            .collect();
+        let offset = parse_offset(&request.next_token)?;
+        let end = (offset + self.page_limit(request.limit)).min(events.len());
+        Ok(FilterLogEventsResponse {
+            events: Some(events[offset.min(end)..end].to_vec()),
+            next_token: if end < events.len() { Some(end.to_string()) } else { None },
+            searched_log_streams: None,
+        })
+    }
+
+    async fn get_log_events(&self, request: GetLogEventsRequest) -> Result<GetLogEventsResponse> {
+        self.check_error()?;
+        if !self.has_log_group(&request.log_group_name) {
+            return Err(anyhow!("ResourceNotFoundException: {}", request.log_group_name));
+        }
+        let events: Vec<OutputLogEvent> = self.get_events(&request.log_group_name)
+            .into_iter()
+            .filter(|e| e.log_stream_name.as_deref() == Some(request.log_stream_name.as_str()))
+            // the end time of GetLogEvents is exclusive
+            .filter(|e| in_range(e.timestamp, request.start_time, request.end_time.map(|end| end - 1)))
+            .map(|e| OutputLogEvent {
+                ingestion_time: e.ingestion_time,
+                message: e.message,
+                timestamp: e.timestamp,
+            })
+            .collect();
+        let limit = self.page_limit(request.limit);
+        // tokens are "f/<index>" (forward) or "b/<index>" (backward)
+        let (start, end) = match request.next_token.as_deref() {
+            Some(token) if token.starts_with("f/") => {
+                let start: usize = token[2..].parse().map_err(|_| anyhow!("invalid next token: {}", token))?;
+                let start = start.min(events.len());
+                (start, (start + limit).min(events.len()))
+            },
+            Some(token) if token.starts_with("b/") => {
+                let end: usize = token[2..].parse().map_err(|_| anyhow!("invalid next token: {}", token))?;
+                let end = end.min(events.len());
+                (end.saturating_sub(limit), end)
+            },
+            Some(token) => return Err(anyhow!("invalid next token: {}", token)),
+            None => {
+                if request.start_from_head == Some(true) {
+                    (0, limit.min(events.len()))
+                } else {
+                    (events.len().saturating_sub(limit), events.len())
+                }
+            },
+        };
+        Ok(GetLogEventsResponse {
+            events: Some(events[start..end].to_vec()),
+            next_backward_token: Some(format!("b/{}", start)),
+            next_forward_token: Some(format!("f/{}", end)),
+        })
+    }
+
+    async fn start_query(&self, request: StartQueryRequest) -> Result<StartQueryResponse> {
+        self.check_error()?;
+        let mut names = request.log_group_names.clone().unwrap_or_default();
+        names.extend(request.log_group_name.clone());
+        if let Some(name) = names.iter().find(|n| !self.has_log_group(n)) {
+            return Err(anyhow!("ResourceNotFoundException: {}", name));
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let polls = inner.query_running_polls;
+        inner.queries.push((String::from("Running"), polls));
+        inner.query_requests.push(request);
+        Ok(StartQueryResponse {
+            query_id: Some(format!("query-{}", inner.queries.len() - 1)),
+        })
+    }
+
+    async fn get_query_results(&self, request: GetQueryResultsRequest) -> Result<GetQueryResultsResponse> {
+        self.check_error()?;
+        let mut inner = self.inner.lock().unwrap();
+        let results = inner.query_results.clone();
+        let query = query_index(&request.query_id)
+            .and_then(|idx| inner.queries.get_mut(idx))
+            .ok_or_else(|| anyhow!("ResourceNotFoundException: {}", request.query_id))?;
+        if query.0 == "Running" {
+            if query.1 == 0 {
+                query.0 = String::from("Complete");
+            } else {
+                query.1 -= 1;
+            }
+        }
+        Ok(GetQueryResultsResponse {
+            results: if query.0 == "Complete" { Some(results) } else { Some(vec![]) },
+            statistics: None,
+            status: Some(query.0.clone()),
+        })
+    }
+
+    async fn stop_query(&self, request: StopQueryRequest) -> Result<StopQueryResponse> {
+        self.check_error()?;
+        let mut inner = self.inner.lock().unwrap();
+        let query = query_index(&request.query_id)
+            .and_then(|idx| inner.queries.get_mut(idx))
+            .ok_or_else(|| anyhow!("ResourceNotFoundException: {}", request.query_id))?;
+        if query.0 != "Running" {
+            return Err(anyhow!("InvalidParameterException: query is not running"));
+        }
+        query.0 = String::from("Cancelled");
+        Ok(StopQueryResponse {
+            success: Some(true),
+        })
+    }
+}
+
+fn query_index(query_id: &str) -> Option<usize> {
+    query_id.strip_prefix("query-").and_then(|idx| idx.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_source() -> InMemoryLogSource {
+        let source = InMemoryLogSource::new(2);
+        source.add_log_group("group1");
+        source.add_log_group("group2");
+        source.add_log_event("group1", "stream1", 1000, "INFO start");
+        source.add_log_event("group1", "stream2", 2000, "ERROR failed");
+        source.add_log_event("group1", "stream1", 3000, "INFO end");
+        source.add_log_event("group2", "stream1", 1500, "other group");
+        source
+    }
+
+    #[tokio::test]
+    async fn can_paginate_log_groups() {
+        let source = make_source();
+        source.add_log_group("group3");
+        let res = source.describe_log_groups(DescribeLogGroupsRequest::default()).await.unwrap();
+        assert_eq!(2, res.log_groups.unwrap().len());
+        let request = DescribeLogGroupsRequest {
+            next_token: res.next_token,
+            ..Default::default()
+        };
+        let res = source.describe_log_groups(request).await.unwrap();
+        assert_eq!(1, res.log_groups.unwrap().len());
+        assert_eq!(None, res.next_token);
+    }
+
+    #[tokio::test]
+    async fn can_filter_log_events() {
+        let source = make_source();
+        let mut request = FilterLogEventsRequest {
+            log_group_name: String::from("group1"),
+            filter_pattern: Some(String::from("INFO")),
+            ..Default::default()
+        };
+        let res = source.filter_log_events(request.clone()).await.unwrap();
+        let messages: Vec<String> = res.events.unwrap().into_iter().map(|e| e.message.unwrap()).collect();
+        assert_eq!(vec![String::from("INFO start"), String::from("INFO end")], messages);
+        request.filter_pattern = None;
+        request.start_time = Some(1500);
+        request.log_stream_name_prefix = Some(String::from("stream1"));
+        let res = source.filter_log_events(request).await.unwrap();
+        let messages: Vec<String> = res.events.unwrap().into_iter().map(|e| e.message.unwrap()).collect();
+        assert_eq!(vec![String::from("INFO end")], messages);
+        assert_eq!(2, source.get_filter_requests().len());
+    }
+
+    #[tokio::test]
+    async fn unknown_log_group_is_error() {
+        let source = make_source();
+        let request = FilterLogEventsRequest {
+            log_group_name: String::from("unknown"),
+            ..Default::default()
+        };
+        assert!(source.filter_log_events(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_page_stream_events_forward_and_backward() {
+        let source = make_source();
+        let mut request = GetLogEventsRequest {
+            log_group_name: String::from("group1"),
+            log_stream_name: String::from("stream1"),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let res = source.get_log_events(request.clone()).await.unwrap();
+        assert_eq!(Some(3000), res.events.unwrap()[0].timestamp);
+        request.next_token = res.next_backward_token;
+        let res = source.get_log_events(request.clone()).await.unwrap();
+        assert_eq!(Some(1000), res.events.unwrap()[0].timestamp);
+        request.next_token = res.next_forward_token;
+        let res = source.get_log_events(request).await.unwrap();
+        assert_eq!(Some(3000), res.events.unwrap()[0].timestamp);
+    }
+
+    #[tokio::test]
+    async fn can_describe_log_streams_by_last_event_time() {
+        let source = make_source();
+        let request = DescribeLogStreamsRequest {
+            log_group_name: String::from("group1"),
+            order_by: Some(String::from("LastEventTime")),
+            descending: Some(true),
+            ..Default::default()
+        };
+        let res = source.describe_log_streams(request).await.unwrap();
+        let names: Vec<String> = res.log_streams.unwrap().into_iter().map(|s| s.log_stream_name.unwrap()).collect();
+        assert_eq!(vec![String::from("stream1"), String::from("stream2")], names);
+    }
+
+    #[tokio::test]
+    async fn can_run_and_stop_queries() {
+        let source = make_source();
+        source.set_query_results(vec![vec![("count(*)", "3")]]);
+        source.set_query_running_polls(1);
+        let request = StartQueryRequest {
+            log_group_names: Some(vec![String::from("group1")]),
+            query_string: String::from("stats count(*)"),
+            ..Default::default()
+        };
+        let query_id = source.start_query(request.clone()).await.unwrap().query_id.unwrap();
+        let get_request = GetQueryResultsRequest { query_id };
+        let res = source.get_query_results(get_request.clone()).await.unwrap();
+        assert_eq!(Some(String::from("Running")), res.status);
+        let res = source.get_query_results(get_request).await.unwrap();
+        assert_eq!(Some(String::from("Complete")), res.status);
+        assert_eq!(Some(String::from("3")), res.results.unwrap()[0][0].value);
+
+        let query_id = source.start_query(request).await.unwrap().query_id.unwrap();
+        assert!(source.stop_query(StopQueryRequest { query_id: query_id.clone() }).await.unwrap().success.unwrap());
+        let res = source.get_query_results(GetQueryResultsRequest { query_id }).await.unwrap();
+        assert_eq!(Some(String::from("Cancelled")), res.status);
+        assert_eq!(2, source.get_query_requests().len());
+    }
+}
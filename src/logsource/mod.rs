@@ -0,0 +1,39 @@
+use rusoto_logs::{
+    DescribeLogGroupsRequest,
+    DescribeLogGroupsResponse,
+    DescribeLogStreamsRequest,
+    DescribeLogStreamsResponse,
+    FilterLogEventsRequest,
+    FilterLogEventsResponse,
+    GetLogEventsRequest,
+    GetLogEventsResponse,
+    GetQueryResultsRequest,
+    GetQueryResultsResponse,
+    StartQueryRequest,
+    StartQueryResponse,
+    StopQueryRequest,
+    StopQueryResponse,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub mod memory;
+pub mod rusoto;
+
+/// backend which log groups and log events are fetched from
+#[async_trait]
+pub trait LogSource: Send + Sync {
+    async fn describe_log_groups(&self, request: DescribeLogGroupsRequest) -> Result<DescribeLogGroupsResponse>;
+
+    async fn describe_log_streams(&self, request: DescribeLogStreamsRequest) -> Result<DescribeLogStreamsResponse>;
+
+    async fn filter_log_events(&self, request: FilterLogEventsRequest) -> Result<FilterLogEventsResponse>;
+
+    async fn get_log_events(&self, request: GetLogEventsRequest) -> Result<GetLogEventsResponse>;
+
+    async fn start_query(&self, request: StartQueryRequest) -> Result<StartQueryResponse>;
+
+    async fn get_query_results(&self, request: GetQueryResultsRequest) -> Result<GetQueryResultsResponse>;
+
+    async fn stop_query(&self, request: StopQueryRequest) -> Result<StopQueryResponse>;
+}
@@ -0,0 +1,73 @@
+use rusoto_logs::{
+    CloudWatchLogs,
+    DescribeLogGroupsRequest,
+    DescribeLogGroupsResponse,
+    DescribeLogStreamsRequest,
+    DescribeLogStreamsResponse,
+    FilterLogEventsRequest,
+    FilterLogEventsResponse,
+    GetLogEventsRequest,
+    GetLogEventsResponse,
+    GetQueryResultsRequest,
+    GetQueryResultsResponse,
+    StartQueryRequest,
+    StartQueryResponse,
+    StopQueryRequest,
+    StopQueryResponse,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use super::LogSource;
+use crate::aws::ClientHolder;
+
+/// CloudWatch Logs backend
+#[derive(Clone)]
+pub struct RusotoLogSource {
+    client_holder: ClientHolder,
+}
+
+impl RusotoLogSource {
+    pub fn new(client_holder: ClientHolder) -> Self {
+        Self {
+            client_holder,
+        }
+    }
+}
+
+#[async_trait]
+impl LogSource for RusotoLogSource {
+    async fn describe_log_groups(&self, request: DescribeLogGroupsRequest) -> Result<DescribeLogGroupsResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.describe_log_groups(request.clone())).await
+    }
+
+    async fn describe_log_streams(&self, request: DescribeLogStreamsRequest) -> Result<DescribeLogStreamsResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.describe_log_streams(request.clone())).await
+    }
+
+    async fn filter_log_events(&self, request: FilterLogEventsRequest) -> Result<FilterLogEventsResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.filter_log_events(request.clone())).await
+    }
+
+    async fn get_log_events(&self, request: GetLogEventsRequest) -> Result<GetLogEventsResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.get_log_events(request.clone())).await
+    }
+
+    async fn start_query(&self, request: StartQueryRequest) -> Result<StartQueryResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.start_query(request.clone())).await
+    }
+
+    async fn get_query_results(&self, request: GetQueryResultsRequest) -> Result<GetQueryResultsResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.get_query_results(request.clone())).await
+    }
+
+    async fn stop_query(&self, request: StopQueryRequest) -> Result<StopQueryResponse> {
+        let client = self.client_holder.logs_client()?;
+        self.client_holder.send(|| client.stop_query(request.clone())).await
+    }
+}
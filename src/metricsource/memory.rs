@@ -0,0 +1,226 @@
+use rusoto_cloudwatch::{
+    AlarmHistoryItem,
+    DescribeAlarmHistoryInput,
+    DescribeAlarmHistoryOutput,
+    DescribeAlarmsInput,
+    DescribeAlarmsOutput,
+    Dimension,
+    GetMetricDataInput,
+    GetMetricDataOutput,
+    ListMetricsInput,
+    ListMetricsOutput,
+    Metric,
+    MetricAlarm,
+    MetricDataResult,
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use super::MetricSource;
+
+#[derive(Debug, Default)]
+struct Inner {
+    metrics: Vec<Metric>,
+    list_requests: Vec<ListMetricsInput>,
+    /// metric, timestamp in epoch millis and value
+    datapoints: Vec<(Metric, i64, f64)>,
+    data_requests: Vec<GetMetricDataInput>,
+    alarms: Vec<MetricAlarm>,
+    alarm_history: Vec<AlarmHistoryItem>,
+}
+
+/// in-memory backend mainly for tests.
+/// clones share the same data, so metrics can be added while it's used
+#[derive(Debug, Clone)]
+pub struct InMemoryMetricSource {
+    inner: Arc<Mutex<Inner>>,
+    page_size: usize,
+}
+
+impl InMemoryMetricSource {
+    /// `page_size` is the max number of items in a page
+    pub fn new(page_size: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            page_size,
+        }
+    }
+
+    pub fn add_metric(&self, namespace: &str, metric_name: &str, dimensions: &[(&str, &str)]) {
+        let metric = make_metric(namespace, metric_name, dimensions);
+        self.inner.lock().unwrap().metrics.push(metric);
+    }
+
+    /// add a datapoint. the datapoints are returned as they are (not aggregated by period)
+    pub fn add_datapoint(&self, metric: &Metric, timestamp: i64, value: f64) {
+        self.inner.lock().unwrap().datapoints.push((metric.clone(), timestamp, value));
+    }
+
+    /// add an alarm returned by `describe_alarms`
+    pub fn add_alarm(&self, alarm: MetricAlarm) {
+        self.inner.lock().unwrap().alarms.push(alarm);
+    }
+
+    /// add a history item of the alarm, newest first
+    pub fn add_alarm_history(&self, alarm_name: &str, timestamp: &str, summary: &str) {
+        let item = AlarmHistoryItem {
+            alarm_name: Some(alarm_name.to_string()),
+            timestamp: Some(timestamp.to_string()),
+            history_summary: Some(summary.to_string()),
+            history_item_type: Some(String::from("StateUpdate")),
+            ..Default::default()
+        };
+        self.inner.lock().unwrap().alarm_history.push(item);
+    }
+
+    /// requests received by `list_metrics`
+    pub fn get_list_requests(&self) -> Vec<ListMetricsInput> {
+        self.inner.lock().unwrap().list_requests.clone()
+    }
+
+    /// requests received by `get_metric_data`
+    pub fn get_data_requests(&self) -> Vec<GetMetricDataInput> {
+        self.inner.lock().unwrap().data_requests.clone()
+    }
+}
+
+/// metric with the dimensions given as (name, value)
+pub fn make_metric(namespace: &str, metric_name: &str, dimensions: &[(&str, &str)]) -> Metric {
+    Metric {
+        namespace: Some(namespace.to_string()),
+        metric_name: Some(metric_name.to_string()),
+        dimensions: Some(
+            dimensions
+                .iter()
+                .map(|(name, value)| Dimension {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect()
+        ),
+    }
+}
+
+fn parse_time(time: &str) -> Result<i64> {
+    Ok(DateTime::parse_from_rfc3339(time)?.timestamp_millis())
+}
+
+fn parse_offset(token: &Option<String>) -> Result<usize> {
+    match token {
+        Some(token) => token.parse().map_err(|_| anyhow!("invalid next token: {}", token)),
+        None => Ok(0),
+    }
+}
+
+fn matches(filter: &Option<String>, value: &Option<String>) -> bool {
+    match filter {
+        Some(filter) => value.as_ref() == Some(filter),
+        None => true,
+    }
+}
+
+#[async_trait]
+impl MetricSource for InMemoryMetricSource {
+    async fn list_metrics(&self, request: ListMetricsInput) -> Result<ListMetricsOutput> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.list_requests.push(request.clone());
+        let metrics: Vec<Metric> = inner.metrics
+            .iter()
+            .filter(|m| matches(&request.namespace, &m.namespace))
+            .filter(|m| matches(&request.metric_name, &m.metric_name))
+            .cloned()
+            .collect();
+        let offset = parse_offset(&request.next_token)?;
+        let end = (offset + self.page_size).min(metrics.len());
+        Ok(ListMetricsOutput {
+            metrics: Some(metrics[offset.min(end)..end].to_vec()),
+            next_token: if end < metrics.len() { Some(end.to_string()) } else { None },
+        })
+    }
+
+    async fn get_metric_data(&self, request: GetMetricDataInput) -> Result<GetMetricDataOutput> {
+        let start = parse_time(&request.start_time)?;
+        let end = parse_time(&request.end_time)?;
+        let mut inner = self.inner.lock().unwrap();
+        inner.data_requests.push(request.clone());
+        let results = request.metric_data_queries
+            .iter()
+            .map(|query| {
+                let metric = query.metric_stat.as_ref().map(|s| &s.metric);
+                let datapoints: Vec<&(Metric, i64, f64)> = inner.datapoints
+                    .iter()
+                    .filter(|(m, t, _)| Some(m) == metric && *t >= start && *t < end)
+                    .collect();
+                MetricDataResult {
+                    id: Some(query.id.clone()),
+                    label: query.label.clone(),
+                    status_code: Some(String::from("Complete")),
+                    timestamps: Some(datapoints.iter().map(|(_, t, _)| Utc.timestamp_millis(*t).to_rfc3339()).collect()),
+                    values: Some(datapoints.iter().map(|(_, _, v)| *v).collect()),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        Ok(GetMetricDataOutput {
+            metric_data_results: Some(results),
+            ..Default::default()
+        })
+    }
+
+    async fn describe_alarms(&self, request: DescribeAlarmsInput) -> Result<DescribeAlarmsOutput> {
+        let alarms: Vec<MetricAlarm> = self.inner.lock().unwrap().alarms
+            .iter()
+            .filter(|a| match &request.alarm_name_prefix {
+                Some(prefix) => a.alarm_name.as_deref().unwrap_or("").starts_with(prefix.as_str()),
+                None => true,
+            })
+            .filter(|a| matches(&request.state_value, &a.state_value))
+            .cloned()
+            .collect();
+        let offset = parse_offset(&request.next_token)?;
+        let end = (offset + self.page_size).min(alarms.len());
+        Ok(DescribeAlarmsOutput {
+            metric_alarms: Some(alarms[offset.min(end)..end].to_vec()),
+            next_token: if end < alarms.len() { Some(end.to_string()) } else { None },
+            ..Default::default()
+        })
+    }
+
+    async fn describe_alarm_history(&self, request: DescribeAlarmHistoryInput) -> Result<DescribeAlarmHistoryOutput> {
+        let items: Vec<AlarmHistoryItem> = self.inner.lock().unwrap().alarm_history
+            .iter()
+            .filter(|i| matches(&request.alarm_name, &i.alarm_name))
+            .cloned()
+            .collect();
+        Ok(DescribeAlarmHistoryOutput {
+            alarm_history_items: Some(items),
+            next_token: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_list_metrics_by_namespace_and_name() {
+        let source = InMemoryMetricSource::new(1);
+        source.add_metric("AWS/Lambda", "Errors", &[("FunctionName", "f1")]);
+        source.add_metric("AWS/Lambda", "Errors", &[("FunctionName", "f2")]);
+        source.add_metric("AWS/Lambda", "Invocations", &[("FunctionName", "f1")]);
+        source.add_metric("AWS/EC2", "CPUUtilization", &[]);
+        let request = ListMetricsInput {
+            namespace: Some(String::from("AWS/Lambda")),
+            metric_name: Some(String::from("Errors")),
+            ..Default::default()
+        };
+        let res = source.list_metrics(request.clone()).await.unwrap();
+        assert_eq!(1, res.metrics.unwrap().len());
+        let res = source.list_metrics(ListMetricsInput { next_token: res.next_token, ..request }).await.unwrap();
+        let metric = &res.metrics.unwrap()[0];
+        assert_eq!("f2", metric.dimensions.as_ref().unwrap()[0].value);
+        assert_eq!(None, res.next_token);
+    }
+}
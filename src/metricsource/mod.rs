@@ -0,0 +1,27 @@
+use rusoto_cloudwatch::{
+    DescribeAlarmHistoryInput,
+    DescribeAlarmHistoryOutput,
+    DescribeAlarmsInput,
+    DescribeAlarmsOutput,
+    GetMetricDataInput,
+    GetMetricDataOutput,
+    ListMetricsInput,
+    ListMetricsOutput,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub mod memory;
+pub mod rusoto;
+
+/// backend which CloudWatch metrics are fetched from
+#[async_trait]
+pub trait MetricSource: Send + Sync {
+    async fn list_metrics(&self, request: ListMetricsInput) -> Result<ListMetricsOutput>;
+
+    async fn get_metric_data(&self, request: GetMetricDataInput) -> Result<GetMetricDataOutput>;
+
+    async fn describe_alarms(&self, request: DescribeAlarmsInput) -> Result<DescribeAlarmsOutput>;
+
+    async fn describe_alarm_history(&self, request: DescribeAlarmHistoryInput) -> Result<DescribeAlarmHistoryOutput>;
+}
@@ -0,0 +1,52 @@
+use rusoto_cloudwatch::{
+    CloudWatch,
+    DescribeAlarmHistoryInput,
+    DescribeAlarmHistoryOutput,
+    DescribeAlarmsInput,
+    DescribeAlarmsOutput,
+    GetMetricDataInput,
+    GetMetricDataOutput,
+    ListMetricsInput,
+    ListMetricsOutput,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use super::MetricSource;
+use crate::aws::ClientHolder;
+
+/// CloudWatch backend
+#[derive(Clone)]
+pub struct RusotoMetricSource {
+    client_holder: ClientHolder,
+}
+
+impl RusotoMetricSource {
+    pub fn new(client_holder: ClientHolder) -> Self {
+        Self {
+            client_holder,
+        }
+    }
+}
+
+#[async_trait]
+impl MetricSource for RusotoMetricSource {
+    async fn list_metrics(&self, request: ListMetricsInput) -> Result<ListMetricsOutput> {
+        let client = self.client_holder.cloudwatch_client()?;
+        self.client_holder.send(|| client.list_metrics(request.clone())).await
+    }
+
+    async fn get_metric_data(&self, request: GetMetricDataInput) -> Result<GetMetricDataOutput> {
+        let client = self.client_holder.cloudwatch_client()?;
+        self.client_holder.send(|| client.get_metric_data(request.clone())).await
+    }
+
+    async fn describe_alarms(&self, request: DescribeAlarmsInput) -> Result<DescribeAlarmsOutput> {
+        let client = self.client_holder.cloudwatch_client()?;
+        self.client_holder.send(|| client.describe_alarms(request.clone())).await
+    }
+
+    async fn describe_alarm_history(&self, request: DescribeAlarmHistoryInput) -> Result<DescribeAlarmHistoryOutput> {
+        let client = self.client_holder.cloudwatch_client()?;
+        self.client_holder.send(|| client.describe_alarm_history(request.clone())).await
+    }
+}
@@ -0,0 +1,308 @@
+use rusoto_logs::{FilterLogEventsRequest, FilteredLogEvent};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::aws::config::AwsConfig;
+use crate::logsource::LogSource;
+use crate::utils::parse_duration;
+
+/// default of how far a poll looks back before the cursor for the events which are ingested late
+pub const TAIL_LOOK_BACK: Duration = Duration::from_secs(60);
+
+/// max number of the events kept by the tail. the oldest ones are dropped
+pub const MAX_TAIL_EVENTS: usize = 10_000;
+
+/// default interval between the polls
+pub const TAIL_INTERVAL: Duration = Duration::from_secs(1);
+
+const MIN_TAIL_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_TAIL_INTERVAL: Duration = Duration::from_secs(600);
+const TAIL_LOOK_BACK_STEP: Duration = Duration::from_secs(15);
+const MAX_TAIL_LOOK_BACK: Duration = Duration::from_secs(600);
+
+/// keys of the profile in the aws config file, which let accounts with less API quota poll less often
+const INTERVAL_CONFIG_KEY: &str = "megane_tail_interval";
+const LOOK_BACK_CONFIG_KEY: &str = "megane_tail_look_back";
+
+/// how often and how far back the tail polls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TailSettings {
+    pub interval: Duration,
+    pub look_back: Duration,
+}
+
+impl Default for TailSettings {
+    fn default() -> Self {
+        Self {
+            interval: TAIL_INTERVAL,
+            look_back: TAIL_LOOK_BACK,
+        }
+    }
+}
+
+impl TailSettings {
+    /// the values of the command line, or the values of the profile in the aws config file
+    pub fn resolve(interval: Option<&str>, look_back: Option<&str>, config: &AwsConfig, profile: &str) -> Result<Self> {
+        let mut settings = Self::default();
+        if let Some(interval) = interval.or_else(|| config.get(profile, INTERVAL_CONFIG_KEY)) {
+            settings.interval = parse_duration(interval)?.max(MIN_TAIL_INTERVAL).min(MAX_TAIL_INTERVAL);
+        }
+        if let Some(look_back) = look_back.or_else(|| config.get(profile, LOOK_BACK_CONFIG_KEY)) {
+            settings.look_back = parse_duration(look_back)?.min(MAX_TAIL_LOOK_BACK);
+        }
+        Ok(settings)
+    }
+
+    /// double (`slower`) or halve the interval
+    pub fn change_interval(&mut self, slower: bool) {
+        let interval = if slower { self.interval * 2 } else { self.interval / 2 };
+        self.interval = interval.max(MIN_TAIL_INTERVAL).min(MAX_TAIL_INTERVAL);
+    }
+
+    /// widen or narrow the look-back by a step
+    pub fn change_look_back(&mut self, wider: bool) {
+        self.look_back = if wider {
+            (self.look_back + TAIL_LOOK_BACK_STEP).min(MAX_TAIL_LOOK_BACK)
+        } else {
+            self.look_back.checked_sub(TAIL_LOOK_BACK_STEP).unwrap_or_default()
+        };
+    }
+}
+
+/// log group and filters which are tailed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TailTarget {
+    pub log_group_name: String,
+    pub filter_pattern: Option<String>,
+    pub log_stream_name_prefix: Option<String>,
+}
+
+/// position of the tail: the latest timestamp returned so far and the event ids seen around it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TailCursor {
+    pub timestamp: Option<i64>,
+    /// event ids and their timestamps, which are kept while they're within the look-back of the timestamp
+    seen: HashMap<String, i64>,
+}
+
+impl TailCursor {
+    /// remember the events and return the ones which haven't been seen
+    fn advance(&mut self, events: Vec<FilteredLogEvent>, look_back: Duration) -> Vec<FilteredLogEvent> {
+        let mut new_events = vec![];
+        for event in events {
+            if let Some(event_id) = &event.event_id {
+                if self.seen.contains_key(event_id) {
+                    continue;
+                }
+                self.seen.insert(event_id.clone(), event.timestamp.unwrap_or(0));
+            }
+            if let Some(timestamp) = event.timestamp {
+                self.timestamp = Some(self.timestamp.map_or(timestamp, |t| t.max(timestamp)));
+            }
+            new_events.push(event);
+        }
+        if let Some(timestamp) = self.timestamp {
+            let oldest = timestamp - look_back.as_millis() as i64;
+            self.seen.retain(|_, t| *t >= oldest);
+        }
+        new_events
+    }
+
+    pub fn seen_len(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+/// fetches the events which are newer than the cursor.
+/// every poll pages FilterLogEvents from the cursor minus the look-back until the results are exhausted
+pub struct TailEngine<S: LogSource> {
+    source: S,
+    target: TailTarget,
+    /// how far the first poll looks back from now
+    since: Duration,
+    look_back: Duration,
+    cursor: TailCursor,
+}
+
+impl<S: LogSource> TailEngine<S> {
+    pub fn new(source: S, target: TailTarget, since: Duration) -> Self {
+        Self {
+            source,
+            target,
+            since,
+            look_back: TAIL_LOOK_BACK,
+            cursor: TailCursor::default(),
+        }
+    }
+
+    pub fn set_look_back(&mut self, look_back: Duration) {
+        self.look_back = look_back;
+    }
+
+    pub fn get_target(&self) -> &TailTarget {
+        &self.target
+    }
+
+    pub fn get_cursor(&self) -> &TailCursor {
+        &self.cursor
+    }
+
+    /// returns the new events in timestamp order
+    pub async fn poll(&mut self, now: i64) -> Result<Vec<FilteredLogEvent>> {
+        let start = match self.cursor.timestamp {
+            Some(timestamp) => timestamp - self.look_back.as_millis() as i64,
+            None => now - self.since.as_millis() as i64,
+        };
+        let mut events = vec![];
+        let mut next_token = None;
+        loop {
+            let request = FilterLogEventsRequest {
+                log_group_name: self.target.log_group_name.clone(),
+                filter_pattern: self.target.filter_pattern.clone(),
+                log_stream_name_prefix: self.target.log_stream_name_prefix.clone(),
+                start_time: Some(start.max(0)),
+                end_time: Some(now),
+                next_token,
+                ..Default::default()
+            };
+            let response = self.source.filter_log_events(request).await?;
+            events.append(&mut response.events.unwrap_or_default());
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        events.sort_by_key(|e| (e.timestamp, e.event_id.clone()));
+        let events = self.cursor.advance(events, self.look_back);
+        // nothing older than the look-back of the first poll is fetched once the tail has started
+        if self.cursor.timestamp.is_none() {
+            self.cursor.timestamp = Some(now);
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logsource::memory::InMemoryLogSource;
+
+    const NOW: i64 = 1_600_000_000_000;
+
+    fn messages(events: &[FilteredLogEvent]) -> Vec<String> {
+        events.iter().map(|e| e.message.clone().unwrap()).collect()
+    }
+
+    fn make_engine(source: &InMemoryLogSource, since: Duration) -> TailEngine<InMemoryLogSource> {
+        let target = TailTarget {
+            log_group_name: String::from("group"),
+            ..Default::default()
+        };
+        TailEngine::new(source.clone(), target, since)
+    }
+
+    fn make_source(page_size: usize) -> InMemoryLogSource {
+        let source = InMemoryLogSource::new(page_size);
+        source.add_log_group("group");
+        source
+    }
+
+    #[tokio::test]
+    async fn pages_until_exhausted_and_returns_only_new_events() {
+        let source = make_source(2);
+        source.add_log_event("group", "stream", NOW - 600_000, "too old");
+        source.add_log_event("group", "stream", NOW - 200_000, "since");
+        source.add_log_event("group", "stream", NOW - 1_000, "first");
+        source.add_log_event("group", "stream", NOW - 500, "second");
+        source.add_log_event("group", "stream", NOW - 100, "third");
+        let mut engine = make_engine(&source, Duration::from_secs(300));
+        let events = engine.poll(NOW).await.unwrap();
+        assert_eq!(vec!["since", "first", "second", "third"], messages(&events));
+        assert_eq!(Some(NOW - 100), engine.get_cursor().timestamp);
+
+        source.add_log_event("group", "stream", NOW + 500, "fourth");
+        let events = engine.poll(NOW + 1_000).await.unwrap();
+        assert_eq!(vec!["fourth"], messages(&events));
+        assert!(engine.poll(NOW + 2_000).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_burst_at_the_same_millisecond() {
+        let source = make_source(3);
+        for i in 0..5 {
+            source.add_log_event("group", "stream", NOW - 100, &format!("burst{}", i));
+        }
+        let mut engine = make_engine(&source, Duration::from_secs(60));
+        engine.set_look_back(Duration::from_secs(0));
+        assert_eq!(5, engine.poll(NOW).await.unwrap().len());
+        // more events at the millisecond of the cursor arrive after the poll
+        source.add_log_event("group", "stream", NOW - 100, "burst5");
+        source.add_log_event("group", "stream", NOW + 100, "after");
+        let events = engine.poll(NOW + 1_000).await.unwrap();
+        assert_eq!(vec!["burst5", "after"], messages(&events));
+        // only the events at the millisecond of the cursor are remembered without the look-back
+        assert_eq!(1, engine.get_cursor().seen_len());
+    }
+
+    #[tokio::test]
+    async fn picks_up_late_events_within_look_back() {
+        let source = make_source(10);
+        source.add_log_event("group", "stream", NOW - 1_000, "first");
+        let mut engine = make_engine(&source, Duration::from_secs(60));
+        engine.set_look_back(Duration::from_secs(10));
+        assert_eq!(vec!["first"], messages(&engine.poll(NOW).await.unwrap()));
+        // ingested late with the timestamps before the cursor
+        source.add_log_event("group", "stream", NOW - 5_000, "late");
+        source.add_log_event("group", "stream", NOW - 20_000, "too late");
+        assert_eq!(vec!["late"], messages(&engine.poll(NOW + 1_000).await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn starts_from_now_when_there_is_no_event() {
+        let source = make_source(10);
+        let mut engine = make_engine(&source, Duration::from_secs(60));
+        engine.set_look_back(Duration::from_secs(0));
+        assert!(engine.poll(NOW).await.unwrap().is_empty());
+        source.add_log_event("group", "stream", NOW - 30_000, "before start");
+        source.add_log_event("group", "stream", NOW + 500, "new");
+        assert_eq!(vec!["new"], messages(&engine.poll(NOW + 1_000).await.unwrap()));
+        assert_eq!(Some(NOW + 500), engine.get_cursor().timestamp);
+    }
+
+    #[test]
+    fn can_resolve_and_change_settings() {
+        let config = AwsConfig::parse("[profile slow]\nmegane_tail_interval = 30s\nmegane_tail_look_back = 2m\n");
+        assert_eq!(TailSettings::default(), TailSettings::resolve(None, None, &config, "default").unwrap());
+        let mut settings = TailSettings::resolve(None, None, &config, "slow").unwrap();
+        assert_eq!(TailSettings { interval: Duration::from_secs(30), look_back: Duration::from_secs(120) }, settings);
+        let settings2 = TailSettings::resolve(Some("100ms"), Some("0s"), &config, "slow").unwrap();
+        assert_eq!(TailSettings { interval: MIN_TAIL_INTERVAL, look_back: Duration::from_secs(0) }, settings2);
+        assert!(TailSettings::resolve(Some("fast"), None, &config, "slow").is_err());
+        settings.change_interval(true);
+        assert_eq!(Duration::from_secs(60), settings.interval);
+        settings.change_interval(false);
+        settings.change_interval(false);
+        assert_eq!(Duration::from_secs(15), settings.interval);
+        settings.change_look_back(true);
+        assert_eq!(Duration::from_secs(135), settings.look_back);
+        for _ in 0..10 {
+            settings.change_look_back(false);
+        }
+        assert_eq!(Duration::from_secs(0), settings.look_back);
+    }
+
+    #[tokio::test]
+    async fn can_filter_events() {
+        let source = make_source(10);
+        source.add_log_event("group", "stream", NOW - 1_000, "INFO ok");
+        source.add_log_event("group", "stream", NOW - 500, "ERROR ng");
+        source.add_log_event("group", "other", NOW - 500, "ERROR other");
+        let target = TailTarget {
+            log_group_name: String::from("group"),
+            filter_pattern: Some(String::from("ERROR")),
+            log_stream_name_prefix: Some(String::from("str")),
+        };
+        let mut engine = TailEngine::new(source, target, Duration::from_secs(60));
+        assert_eq!(vec!["ERROR ng"], messages(&engine.poll(NOW).await.unwrap()));
+    }
+}
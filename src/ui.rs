@@ -1,14 +1,18 @@
 use super::app::App;
+use super::components::Drawable;
+use super::utils;
 use tui::{
     backend::CrosstermBackend,
     widgets::{
         Block,
         Borders,
+        Clear,
         Tabs,
     },
     layout::{
         Layout,
         Constraint,
+        Rect,
     },
     text::Spans,
     style::{Style, Color},
@@ -23,7 +27,7 @@ pub fn draw(f: &mut Frame<CrosstermBackend<Stdout>>, app: &mut App) {
         .split(f.size());
     let titles = ["LOGS", "METRICS"].iter().cloned().map(Spans::from).collect();
     let tabs = Tabs::new(titles)
-        .block(Block::default().borders(Borders::ALL).title(""))
+        .block(Block::default().borders(Borders::ALL).title(get_title(app)))
         .highlight_style(Style::default().fg(Color::Yellow))
         .select(app.current_tab_idx);
     f.render_widget(tabs, chunks[0]);
@@ -32,5 +36,31 @@ pub fn draw(f: &mut Frame<CrosstermBackend<Stdout>>, app: &mut App) {
     if let Some(tab) = app.tabs.get_mut(app.current_tab_idx) {
         tab.draw(f, chunks[1]);
     }
+
+    // draw popup
+    if let Some(popup) = app.region_popup.as_mut() {
+        popup.draw(f, f.size());
+    }
+    if let Some(popup) = app.profile_popup.as_mut() {
+        popup.draw(f, f.size());
+    }
+    if let Some(input) = app.mfa_input.as_mut() {
+        let area = utils::centered_rect(60, 100, f.size());
+        let area = Rect::new(area.x, area.y + area.height.saturating_sub(3) / 2, area.width, 3.min(area.height));
+        f.render_widget(Clear, area);
+        input.draw(f, area);
+    }
 }
 
+
+fn get_title(app: &App) -> String {
+    let title = format!(
+        "Profile: {} (Ctrl-p) / Region: {} (Ctrl-r)",
+        app.get_profile_name(),
+        app.get_region_name(),
+    );
+    match app.get_endpoint_url() {
+        Some(endpoint_url) => format!("{} / Endpoint: {}", title, endpoint_url),
+        None => title,
+    }
+}
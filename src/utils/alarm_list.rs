@@ -0,0 +1,225 @@
+use super::StatefulList;
+use tui::widgets::ListState;
+use rusoto_cloudwatch::{Metric, MetricAlarm};
+use super::metric_menulist::format_dimensions;
+
+/// states of alarms which the list can be filtered by
+pub const ALARM_STATES: [&str; 3] = ["ALARM", "OK", "INSUFFICIENT_DATA"];
+
+/// metric which the alarm watches. the first metric of the expression for metric math alarms
+pub fn get_alarm_metric(alarm: &MetricAlarm) -> Option<Metric> {
+    if alarm.metric_name.is_some() {
+        return Some(Metric {
+            namespace: alarm.namespace.clone(),
+            metric_name: alarm.metric_name.clone(),
+            dimensions: Some(alarm.dimensions.clone().unwrap_or_default()),
+        });
+    }
+    alarm.metrics
+        .as_ref()?
+        .iter()
+        .filter_map(|q| q.metric_stat.as_ref())
+        .map(|s| s.metric.clone())
+        .next()
+}
+
+/// summary of the alarm shown above its history
+pub fn get_alarm_detail_text(alarm: &MetricAlarm) -> String {
+    let metric = match get_alarm_metric(alarm) {
+        Some(metric) => format!(
+            "{} / {} ({})",
+            metric.namespace.clone().unwrap_or_default(),
+            metric.metric_name.clone().unwrap_or_default(),
+            format_dimensions(&metric),
+        ),
+        None => String::from("-"),
+    };
+    let condition = format!(
+        "{} {} {} for {} x {}s",
+        alarm.statistic.clone().unwrap_or_else(|| String::from("-")),
+        alarm.comparison_operator.clone().unwrap_or_default(),
+        alarm.threshold.map(|t| t.to_string()).unwrap_or_else(|| String::from("-")),
+        alarm.evaluation_periods.unwrap_or(0),
+        alarm.period.unwrap_or(0),
+    );
+    [
+        format!("Name: {}", alarm.alarm_name.clone().unwrap_or_default()),
+        format!("State: {}", alarm.state_value.clone().unwrap_or_default()),
+        format!("Reason: {}", alarm.state_reason.clone().unwrap_or_default()),
+        format!("Metric: {}", metric),
+        format!("Condition: {}", condition),
+    ].join("\n")
+}
+
+/// list of the alarms filtered by name prefix and state
+#[derive(Debug)]
+pub struct AlarmList {
+    items: Vec<MetricAlarm>,
+    /// alarms which pass the filters. updated whenever the items or the filters change
+    filtered: Vec<MetricAlarm>,
+    prefix: Option<String>,
+    state_filter: Option<String>,
+    state: Option<ListState>,
+}
+
+impl AlarmList {
+    pub fn new(items: Vec<MetricAlarm>) -> Self {
+        let mut list = Self {
+            items,
+            filtered: vec![],
+            prefix: None,
+            state_filter: None,
+            state: Some(ListState::default()),
+        };
+        list.update_filtered();
+        list
+    }
+
+    pub fn set_items(&mut self, items: Vec<MetricAlarm>) {
+        self.items = items;
+        self.update_filtered();
+    }
+
+    pub fn is_same_items(&self, items: &[MetricAlarm]) -> bool {
+        self.items == items
+    }
+
+    /// show only the alarms whose names start with `prefix`
+    pub fn set_prefix(&mut self, prefix: Option<String>) {
+        self.prefix = prefix;
+        self.state = Some(ListState::default());
+        self.update_filtered();
+    }
+
+    pub fn get_state_filter(&self) -> Option<String> {
+        self.state_filter.clone()
+    }
+
+    /// all -> ALARM -> OK -> INSUFFICIENT_DATA -> all
+    pub fn cycle_state_filter(&mut self) {
+        let next_idx = match &self.state_filter {
+            Some(filter) => ALARM_STATES.iter().position(|s| s == filter).map(|i| i + 1).unwrap_or(0),
+            None => 0,
+        };
+        self.state_filter = ALARM_STATES.get(next_idx).map(|s| s.to_string());
+        self.state = Some(ListState::default());
+        self.update_filtered();
+    }
+
+    /// alarms which are shown, in the order of the labels
+    pub fn get_alarms(&self) -> &[MetricAlarm] {
+        &self.filtered
+    }
+
+    fn update_filtered(&mut self) {
+        self.filtered = self.items
+            .iter()
+            .filter(|a| match &self.prefix {
+                Some(prefix) => a.alarm_name.as_deref().unwrap_or("").starts_with(prefix.as_str()),
+                None => true,
+            })
+            .filter(|a| match &self.state_filter {
+                Some(filter) => a.state_value.as_ref() == Some(filter),
+                None => true,
+            })
+            .cloned()
+            .collect();
+    }
+
+    pub fn get_selected_alarm(&self) -> Option<MetricAlarm> {
+        let idx = self.state.as_ref()?.selected()?;
+        self.filtered.get(idx).cloned()
+    }
+}
+
+impl StatefulList for AlarmList {
+    fn get_labels(&self) -> Vec<String> {
+        self.get_alarms()
+            .iter()
+            .map(|a| format!(
+                "[{}] {}",
+                a.state_value.clone().unwrap_or_default(),
+                a.alarm_name.clone().unwrap_or_default(),
+            ))
+            .collect()
+    }
+    fn get_state(&mut self) -> Option<ListState> {
+        self.state.clone()
+    }
+    fn set_state(&mut self, new_state: ListState) {
+        self.state = Some(new_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudwatch::{Dimension, MetricDataQuery, MetricStat};
+
+    fn make_alarm(name: &str, state: &str) -> MetricAlarm {
+        MetricAlarm {
+            alarm_name: Some(name.to_string()),
+            state_value: Some(state.to_string()),
+            namespace: Some(String::from("AWS/Lambda")),
+            metric_name: Some(String::from("Errors")),
+            dimensions: Some(vec![Dimension { name: String::from("FunctionName"), value: name.to_string() }]),
+            ..Default::default()
+        }
+    }
+
+    fn make_list() -> AlarmList {
+        AlarmList::new(vec![
+            make_alarm("api-errors", "ALARM"),
+            make_alarm("api-latency", "OK"),
+            make_alarm("batch-errors", "ALARM"),
+        ])
+    }
+
+    #[test]
+    fn can_filter_by_prefix_and_state() {
+        let mut list = make_list();
+        list.set_prefix(Some(String::from("api-")));
+        assert_eq!(
+            vec![String::from("[ALARM] api-errors"), String::from("[OK] api-latency")],
+            list.get_labels(),
+        );
+        list.cycle_state_filter();
+        assert_eq!(Some(String::from("ALARM")), list.get_state_filter());
+        assert_eq!(vec![String::from("[ALARM] api-errors")], list.get_labels());
+        list.cycle_state_filter();
+        list.cycle_state_filter();
+        assert!(list.get_labels().is_empty());
+        list.cycle_state_filter();
+        assert_eq!(None, list.get_state_filter());
+        assert_eq!(2, list.get_labels().len());
+        list.set_items(vec![make_alarm("api-errors", "OK")]);
+        assert!(list.is_same_items(&[make_alarm("api-errors", "OK")]));
+        assert_eq!(vec![String::from("[OK] api-errors")], list.get_labels());
+    }
+
+    #[test]
+    fn can_select_alarm() {
+        let mut list = make_list();
+        assert_eq!(None, list.get_selected_alarm());
+        list.next();
+        list.next();
+        assert_eq!(Some(String::from("api-latency")), list.get_selected_alarm().unwrap().alarm_name);
+    }
+
+    #[test]
+    fn can_get_metric_of_alarm() {
+        let alarm = make_alarm("f1", "OK");
+        let metric = get_alarm_metric(&alarm).unwrap();
+        assert_eq!(Some(String::from("Errors")), metric.metric_name);
+        assert_eq!("FunctionName=f1", format_dimensions(&metric));
+        let math_alarm = MetricAlarm {
+            metrics: Some(vec![
+                MetricDataQuery { id: String::from("e1"), expression: Some(String::from("m1 * 100")), ..Default::default() },
+                MetricDataQuery { id: String::from("m1"), metric_stat: Some(MetricStat { metric: metric.clone(), ..Default::default() }), ..Default::default() },
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(Some(metric), get_alarm_metric(&math_alarm));
+        assert_eq!(None, get_alarm_metric(&MetricAlarm::default()));
+    }
+}
@@ -1,12 +1,19 @@
 use super::StatefulTable;
 use tui::widgets::TableState;
 use rusoto_logs::FilteredLogEvent;
-use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::timestamp_format::TimestampFormat;
 
 #[derive(Debug)]
 pub struct LogEventList {
     items: Vec<FilteredLogEvent>,
     state: Option<TableState>,
+    timestamp_format: TimestampFormat,
+    /// whether the log stream name is shown between the timestamp and the message
+    show_log_stream_name: bool,
+    /// log group names of the events by event id, which are shown as a column when the events are merged
+    log_group_names: HashMap<String, String>,
 }
 
 impl LogEventList {
@@ -14,13 +21,43 @@ impl LogEventList {
         Self {
             items,
             state: Some(TableState::default()),
+            timestamp_format: TimestampFormat::default(),
+            show_log_stream_name: false,
+            log_group_names: HashMap::new(),
         }
     }
 
+    pub fn set_timestamp_format(&mut self, timestamp_format: TimestampFormat) {
+        self.timestamp_format = timestamp_format;
+    }
+
+    pub fn set_show_log_stream_name(&mut self, show_log_stream_name: bool) {
+        self.show_log_stream_name = show_log_stream_name;
+    }
+
+    /// formatted timestamp of the event
+    pub fn get_timestamp_label(&self, idx: usize) -> Option<String> {
+        let timestamp = self.items.get(idx)?.timestamp?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+        Some(self.timestamp_format.format(timestamp, now))
+    }
+
     pub fn set_items(&mut self, items: Vec<FilteredLogEvent>) {
         self.items = items;
     }
 
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn get_event(&self, idx: usize) -> Option<&FilteredLogEvent> {
+        self.items.get(idx)
+    }
+
     pub fn get_log_event_text(&self, idx: usize) -> Option<String> {
         if let Some(item) = self.items.get(idx) {
             item.message.clone()
@@ -31,6 +68,7 @@ impl LogEventList {
 
     pub fn clear_items(&mut self) {
         self.items = vec![];
+        self.log_group_names.clear();
         self.state = Some(TableState::default());
     }
 
@@ -38,6 +76,9 @@ impl LogEventList {
         Self {
             items: self.items.clone(),
             state,
+            timestamp_format: self.timestamp_format.clone(),
+            show_log_stream_name: self.show_log_stream_name,
+            log_group_names: self.log_group_names.clone(),
         }
     }
 
@@ -73,6 +114,56 @@ impl LogEventList {
         }
     }
 
+    /// insert the events which are known to be new in timestamp order.
+    /// the log group name is kept for the column if it's given. the selected event stays selected
+    pub fn insert_items(&mut self, log_group_name: Option<&str>, items: Vec<FilteredLogEvent>) {
+        for item in items {
+            if let (Some(log_group_name), Some(event_id)) = (log_group_name, &item.event_id) {
+                self.log_group_names.insert(event_id.clone(), log_group_name.to_string());
+            }
+            let idx = self.items.partition_point(|i| i.timestamp <= item.timestamp);
+            self.items.insert(idx, item);
+            if let Some(state) = self.state.as_mut() {
+                match state.selected() {
+                    Some(selected) if idx <= selected => state.select(Some(selected + 1)),
+                    _ => {},
+                }
+            }
+        }
+    }
+
+    /// drop the oldest events over `max_len`. the selected event stays selected if it's kept
+    pub fn truncate_front(&mut self, max_len: usize) {
+        if self.items.len() <= max_len {
+            return;
+        }
+        let removed = self.items.len() - max_len;
+        for item in self.items.drain(..removed) {
+            if let Some(event_id) = item.event_id {
+                self.log_group_names.remove(&event_id);
+            }
+        }
+        if let Some(state) = self.state.as_mut() {
+            if let Some(selected) = state.selected() {
+                state.select(Some(selected.saturating_sub(removed)));
+            }
+        }
+    }
+
+    /// index of the event
+    pub fn position(&self, event_id: &str) -> Option<usize> {
+        self.items.iter().position(|i| i.event_id.as_deref() == Some(event_id))
+    }
+
+    pub fn has_log_group_names(&self) -> bool {
+        !self.log_group_names.is_empty()
+    }
+
+    pub fn get_log_group_name(&self, idx: usize) -> Option<&String> {
+        let event_id = self.items.get(idx)?.event_id.as_ref()?;
+        self.log_group_names.get(event_id)
+    }
+
     pub fn push_items(&mut self, items: &mut Vec<FilteredLogEvent>, next_token: Option<&String>) {
         if self.items.len() > 0 && self.is_last_more_item() {
             self.items.remove(self.items.len() - 1);
@@ -111,16 +202,23 @@ impl LogEventList {
 
 impl StatefulTable for LogEventList {
     fn get_labels(&self) -> Vec<Vec<String>> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
         self.items
             .iter()
             .map(|i| {
-                let mut vec = Vec::with_capacity(2);
+                let mut vec = Vec::with_capacity(3);
                 if let Some(timestamp) = &i.timestamp {
-                    let dt = Utc.timestamp(*timestamp / 1000, 0);
-                    vec.push(dt.format("%Y-%m-%d %H:%M:%S %Z").to_string());
+                    vec.push(self.timestamp_format.format(*timestamp, now));
                 } else {
                     vec.push(String::from("More..."));
                 }
+                if self.has_log_group_names() {
+                    let log_group_name = i.event_id.as_ref().and_then(|id| self.log_group_names.get(id));
+                    vec.push(log_group_name.cloned().unwrap_or_default());
+                }
+                if self.show_log_stream_name {
+                    vec.push(i.log_stream_name.clone().unwrap_or_default());
+                }
                 if let Some(message) = &i.message {
                     vec.push(String::from(message));
                 } else {
@@ -272,6 +370,63 @@ mod tests {
         assert_eq!(expected, log_event_list.items);
     }
 
+    #[test]
+    fn it_can_show_log_stream_name_column() {
+        let mut events = get_random_events();
+        events[0].log_stream_name = Some(String::from("stream1"));
+        let mut log_event_list = LogEventList::new(events);
+        assert_eq!(2, log_event_list.get_labels()[0].len());
+        log_event_list.set_show_log_stream_name(true);
+        let labels = log_event_list.clone_with_state(None).get_labels();
+        assert_eq!(String::from("stream1"), labels[0][1]);
+        assert_eq!(String::from("message_1"), labels[0][2]);
+        assert_eq!(String::from(""), labels[1][1]);
+    }
+
+    #[test]
+    fn it_can_merge_events_of_log_groups() {
+        let make = |event_id: &str, timestamp: i64| FilteredLogEvent {
+            event_id: Some(event_id.to_string()),
+            timestamp: Some(timestamp),
+            message: Some(event_id.to_string()),
+            ..Default::default()
+        };
+        let mut log_event_list = LogEventList::new(vec![]);
+        log_event_list.insert_items(None, vec![make("a1", 1), make("a3", 3)]);
+        assert!(!log_event_list.has_log_group_names());
+        assert_eq!(2, log_event_list.get_labels()[0].len());
+        log_event_list.insert_items(Some("b"), vec![make("b2", 2), make("b3", 3)]);
+        let labels = log_event_list.get_labels();
+        let messages: Vec<&str> = labels.iter().map(|l| l[2].as_str()).collect();
+        assert_eq!(vec!["a1", "b2", "a3", "b3"], messages);
+        assert_eq!(String::from("b"), labels[1][1]);
+        assert_eq!(Some(&String::from("b")), log_event_list.get_log_group_name(3));
+        assert_eq!(None, log_event_list.get_log_group_name(0));
+    }
+
+    #[test]
+    fn selected_event_is_kept_when_inserting_or_truncating() {
+        let make = |event_id: &str, timestamp: i64| FilteredLogEvent {
+            event_id: Some(event_id.to_string()),
+            timestamp: Some(timestamp),
+            ..Default::default()
+        };
+        let mut log_event_list = LogEventList::new(vec![]);
+        log_event_list.insert_items(Some("a"), vec![make("a1", 1), make("a3", 3), make("a5", 5)]);
+        let mut state = TableState::default();
+        state.select(Some(1));
+        log_event_list.set_state(state);
+        log_event_list.insert_items(Some("b"), vec![make("b2", 2), make("b6", 6), make("b0", 0)]);
+        assert_eq!(Some(3), log_event_list.get_state().unwrap().selected());
+        assert_eq!(Some(3), log_event_list.position("a3"));
+        log_event_list.truncate_front(4);
+        assert_eq!(4, log_event_list.len());
+        assert_eq!(Some(1), log_event_list.get_state().unwrap().selected());
+        assert_eq!(Some(1), log_event_list.position("a3"));
+        assert_eq!(None, log_event_list.position("b0"));
+        assert_eq!(Some(&String::from("b")), log_event_list.get_log_group_name(0));
+    }
+
     #[test]
     fn it_can_clone_with_state() {
         let log_event_list = LogEventList::new(get_default_events());
@@ -369,6 +524,20 @@ mod tests {
         assert_eq!(actual_labels, expected_labels);
     }
 
+    #[test]
+    fn it_can_get_labels_with_timestamp_format() {
+        let mut log_event_list = LogEventList::new(get_random_events());
+        log_event_list.set_timestamp_format(TimestampFormat {
+            format: String::from("%H:%M:%S"),
+            millis: true,
+            ..Default::default()
+        });
+        assert_eq!(String::from("00:00:00.000"), log_event_list.get_labels()[0][0]);
+        let cloned = log_event_list.clone_with_state(None);
+        assert_eq!(Some(String::from("00:00:00.000")), cloned.get_timestamp_label(0));
+        assert_eq!(None, cloned.get_timestamp_label(1));
+    }
+
     #[test]
     fn it_can_get_state() {
         let mut log_event_list = LogEventList::new(get_default_events());
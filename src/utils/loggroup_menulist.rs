@@ -2,10 +2,15 @@ use super::StatefulList;
 use tui::widgets::ListState;
 use rusoto_logs::LogGroup;
 
+/// max number of log groups an Insights query accepts
+pub const MAX_SELECTED_LOG_GROUPS: usize = 50;
+
 #[derive(Debug)]
 pub struct LogGroupMenuList {
     items: Vec<LogGroup>,
     state: Option<ListState>,
+    /// names of the log groups toggled by space, in the toggled order
+    selected: Vec<String>,
 }
 
 impl LogGroupMenuList {
@@ -13,6 +18,7 @@ impl LogGroupMenuList {
         LogGroupMenuList {
             items,
             state: Some(ListState::default()),
+            selected: vec![],
         }
     }
 
@@ -32,9 +38,38 @@ impl LogGroupMenuList {
         Self {
             items: self.items.clone(),
             state,
+            selected: self.selected.clone(),
         }
     }
 
+    /// toggle whether the item is selected.
+    /// returns false if the item can't be selected (e.g. "More..." or too many selected)
+    pub fn toggle_selection(&mut self, idx: usize) -> bool {
+        let name = match self.items.get(idx) {
+            Some(item) if item.arn != Some(String::from("more")) => item.log_group_name.clone(),
+            _ => None,
+        };
+        if let Some(name) = name {
+            if let Some(pos) = self.selected.iter().position(|s| s == &name) {
+                self.selected.remove(pos);
+                return true;
+            }
+            if self.selected.len() < MAX_SELECTED_LOG_GROUPS {
+                self.selected.push(name);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn get_selected_log_group_names(&self) -> Vec<String> {
+        self.selected.clone()
+    }
+
+    pub fn set_selected_log_group_names(&mut self, selected: Vec<String>) {
+        self.selected = selected;
+    }
+
     pub fn push_items(&mut self, mut items: &mut Vec<LogGroup>, next_token: Option<&String>) {
         if self.items.len() > 0 {
             self.items.remove(self.items.len() - 1);
@@ -79,7 +114,11 @@ impl StatefulList for LogGroupMenuList {
             .iter()
             .map(|i| {
                 if let Some(log_group_name) = &i.log_group_name {
-                    String::from(log_group_name)
+                    if self.selected.contains(log_group_name) {
+                        format!("* {}", log_group_name)
+                    } else {
+                        String::from(log_group_name)
+                    }
                 } else {
                     String::from("")
                 }
@@ -135,4 +174,36 @@ mod tests {
         assert_eq!(expected.get_item(1), log_group_list.get_item(1));
         assert_eq!(expected.get_item(2), log_group_list.get_item(2));
     }
+
+    #[test]
+    fn can_toggle_selection() {
+        let mut log_group_list = LogGroupMenuList::new(vec![]);
+        log_group_list.push_items(&mut get_default_log_groups(), Some(&String::from("token")));
+        assert!(log_group_list.toggle_selection(2));
+        assert!(log_group_list.toggle_selection(0));
+        assert!(!log_group_list.toggle_selection(3));
+        assert_eq!(vec![String::from("log_group_3"), String::from("log_group_1")], log_group_list.get_selected_log_group_names());
+        assert_eq!(
+            vec![String::from("* log_group_1"), String::from("log_group_2"), String::from("* log_group_3"), String::from("More...")],
+            log_group_list.get_labels(),
+        );
+        assert!(log_group_list.toggle_selection(2));
+        assert_eq!(vec![String::from("log_group_1")], log_group_list.get_selected_log_group_names());
+    }
+
+    #[test]
+    fn cannot_select_too_many_log_groups() {
+        let log_groups = (0..MAX_SELECTED_LOG_GROUPS + 1)
+            .map(|i| LogGroup {
+                log_group_name: Some(format!("log_group_{}", i)),
+                ..Default::default()
+            })
+            .collect();
+        let mut log_group_list = LogGroupMenuList::new(log_groups);
+        for i in 0..MAX_SELECTED_LOG_GROUPS {
+            assert!(log_group_list.toggle_selection(i));
+        }
+        assert!(!log_group_list.toggle_selection(MAX_SELECTED_LOG_GROUPS));
+        assert_eq!(MAX_SELECTED_LOG_GROUPS, log_group_list.get_selected_log_group_names().len());
+    }
 }
@@ -0,0 +1,74 @@
+use super::StatefulList;
+use tui::widgets::ListState;
+use rusoto_logs::LogStream;
+
+/// log streams of a log group, in the order they're fetched
+#[derive(Debug)]
+pub struct LogStreamMenuList {
+    items: Vec<LogStream>,
+    state: Option<ListState>,
+}
+
+impl LogStreamMenuList {
+    pub fn new(items: Vec<LogStream>) -> Self {
+        Self {
+            items,
+            state: Some(ListState::default()),
+        }
+    }
+
+    pub fn get_log_stream_name(&self, idx: usize) -> Option<String> {
+        self.items.get(idx).and_then(|s| s.log_stream_name.clone())
+    }
+
+    pub fn get_selected_log_stream_name(&self) -> Option<String> {
+        let idx = self.state.as_ref()?.selected()?;
+        self.get_log_stream_name(idx)
+    }
+
+    pub fn clone_with_state(&self, state: Option<ListState>) -> Self {
+        Self {
+            items: self.items.clone(),
+            state,
+        }
+    }
+}
+
+impl StatefulList for LogStreamMenuList {
+    fn get_labels(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .map(|s| s.log_stream_name.clone().unwrap_or_default())
+            .collect()
+    }
+    fn get_state(&mut self) -> Option<ListState> {
+        self.state.clone()
+    }
+    fn set_state(&mut self, new_state: ListState) {
+        self.state = Some(new_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_stream(name: &str) -> LogStream {
+        LogStream {
+            log_stream_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_select_log_stream() {
+        let mut list = LogStreamMenuList::new(vec![make_stream("s1"), make_stream("s2")]);
+        assert_eq!(vec![String::from("s1"), String::from("s2")], list.get_labels());
+        assert_eq!(None, list.get_selected_log_stream_name());
+        list.next();
+        list.next();
+        assert_eq!(Some(String::from("s2")), list.get_selected_log_stream_name());
+        let list = list.clone_with_state(None);
+        assert_eq!(None, list.get_selected_log_stream_name());
+    }
+}
@@ -0,0 +1,190 @@
+use super::StatefulList;
+use tui::widgets::ListState;
+use rusoto_cloudwatch::Metric;
+
+/// level of the metrics browser
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricLevel {
+    Namespace,
+    /// metric names in the namespace
+    MetricName(String),
+    /// dimension sets of the metric (namespace, metric name)
+    Dimensions(String, String),
+}
+
+impl MetricLevel {
+    /// namespace and metric name passed to ListMetrics
+    pub fn get_filter(&self) -> (Option<String>, Option<String>) {
+        match self {
+            MetricLevel::Namespace => (None, None),
+            MetricLevel::MetricName(namespace) => (Some(namespace.clone()), None),
+            MetricLevel::Dimensions(namespace, metric_name) => {
+                (Some(namespace.clone()), Some(metric_name.clone()))
+            },
+        }
+    }
+}
+
+/// "Name=Value, ..." label of the dimensions of the metric
+pub fn format_dimensions(metric: &Metric) -> String {
+    let dimensions = metric.dimensions.clone().unwrap_or_default();
+    if dimensions.is_empty() {
+        String::from("(no dimensions)")
+    } else {
+        dimensions
+            .iter()
+            .map(|d| format!("{}={}", d.name, d.value))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// menu of the metrics browser. the labels are the distinct values of the level
+#[derive(Debug)]
+pub struct MetricMenuList {
+    items: Vec<Metric>,
+    level: MetricLevel,
+    query: Option<String>,
+    state: Option<ListState>,
+}
+
+impl MetricMenuList {
+    pub fn new(items: Vec<Metric>, level: MetricLevel) -> Self {
+        Self {
+            items,
+            level,
+            query: None,
+            state: Some(ListState::default()),
+        }
+    }
+
+    pub fn get_level(&self) -> &MetricLevel {
+        &self.level
+    }
+
+    pub fn set_items(&mut self, items: Vec<Metric>) {
+        self.items = items;
+    }
+
+    pub fn is_same_items(&self, items: &[Metric]) -> bool {
+        self.items == items
+    }
+
+    /// show only the labels which contain `query`
+    pub fn set_query(&mut self, query: Option<String>) {
+        self.query = query;
+        self.state = Some(ListState::default());
+    }
+
+    /// metrics whose labels are shown, in the order of the labels
+    pub fn get_metrics(&self) -> Vec<Metric> {
+        let mut pairs: Vec<(String, Metric)> = vec![];
+        for item in &self.items {
+            let label = self.get_label(item);
+            if self.matches_level(item) && self.matches_query(&label) && !pairs.iter().any(|(l, _)| l == &label) {
+                pairs.push((label, item.clone()));
+            }
+        }
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        pairs.into_iter().map(|(_, metric)| metric).collect()
+    }
+
+    pub fn get_selected_metric(&self) -> Option<Metric> {
+        let idx = self.state.as_ref()?.selected()?;
+        self.get_metrics().get(idx).cloned()
+    }
+
+    fn get_label(&self, metric: &Metric) -> String {
+        match self.level {
+            MetricLevel::Namespace => metric.namespace.clone().unwrap_or_default(),
+            MetricLevel::MetricName(_) => metric.metric_name.clone().unwrap_or_default(),
+            MetricLevel::Dimensions(_, _) => format_dimensions(metric),
+        }
+    }
+
+    fn matches_level(&self, metric: &Metric) -> bool {
+        let (namespace, metric_name) = self.level.get_filter();
+        let namespace_matches = match namespace {
+            Some(namespace) => metric.namespace.as_ref() == Some(&namespace),
+            None => true,
+        };
+        let metric_name_matches = match metric_name {
+            Some(metric_name) => metric.metric_name.as_ref() == Some(&metric_name),
+            None => true,
+        };
+        namespace_matches && metric_name_matches
+    }
+
+    fn matches_query(&self, label: &str) -> bool {
+        match &self.query {
+            Some(query) => label.contains(query.as_str()),
+            None => true,
+        }
+    }
+}
+
+impl StatefulList for MetricMenuList {
+    fn get_labels(&self) -> Vec<String> {
+        self.get_metrics().iter().map(|m| self.get_label(m)).collect()
+    }
+    fn get_state(&mut self) -> Option<ListState> {
+        self.state.clone()
+    }
+    fn set_state(&mut self, new_state: ListState) {
+        self.state = Some(new_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudwatch::Dimension;
+
+    fn make_metric(namespace: &str, metric_name: &str, dimensions: &[(&str, &str)]) -> Metric {
+        Metric {
+            namespace: Some(namespace.to_string()),
+            metric_name: Some(metric_name.to_string()),
+            dimensions: Some(
+                dimensions
+                    .iter()
+                    .map(|(name, value)| Dimension { name: name.to_string(), value: value.to_string() })
+                    .collect()
+            ),
+        }
+    }
+
+    fn make_metrics() -> Vec<Metric> {
+        vec![
+            make_metric("AWS/Lambda", "Invocations", &[("FunctionName", "f1")]),
+            make_metric("AWS/Lambda", "Errors", &[("FunctionName", "f1")]),
+            make_metric("AWS/Lambda", "Errors", &[("FunctionName", "f2"), ("Resource", "f2")]),
+            make_metric("AWS/EC2", "CPUUtilization", &[]),
+        ]
+    }
+
+    #[test]
+    fn labels_are_distinct_values_of_the_level() {
+        let list = MetricMenuList::new(make_metrics(), MetricLevel::Namespace);
+        assert_eq!(vec![String::from("AWS/EC2"), String::from("AWS/Lambda")], list.get_labels());
+        let list = MetricMenuList::new(make_metrics(), MetricLevel::MetricName(String::from("AWS/Lambda")));
+        assert_eq!(vec![String::from("Errors"), String::from("Invocations")], list.get_labels());
+        let list = MetricMenuList::new(make_metrics(), MetricLevel::Dimensions(String::from("AWS/Lambda"), String::from("Errors")));
+        assert_eq!(
+            vec![
+                String::from("FunctionName=f1"),
+                String::from("FunctionName=f2, Resource=f2"),
+            ],
+            list.get_labels(),
+        );
+    }
+
+    #[test]
+    fn can_filter_labels_and_select_metric() {
+        let mut list = MetricMenuList::new(make_metrics(), MetricLevel::Dimensions(String::from("AWS/Lambda"), String::from("Errors")));
+        list.set_query(Some(String::from("f2")));
+        assert_eq!(vec![String::from("FunctionName=f2, Resource=f2")], list.get_labels());
+        assert_eq!(None, list.get_selected_metric());
+        list.next();
+        assert_eq!(Some(make_metrics()[2].clone()), list.get_selected_metric());
+    }
+}
@@ -0,0 +1,179 @@
+use rusoto_cloudwatch::Metric;
+use chrono::{Local, TimeZone};
+use super::metric_menulist::format_dimensions;
+
+/// datapoints of a metric drawn as a line of the chart
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSeries {
+    pub label: String,
+    /// (epoch millis, value) in timestamp order
+    pub points: Vec<(f64, f64)>,
+}
+
+impl MetricSeries {
+    pub fn new(label: String) -> Self {
+        Self {
+            label,
+            points: vec![],
+        }
+    }
+}
+
+/// legend label of the metric (e.g. "Errors FunctionName=f1")
+pub fn get_series_label(metric: &Metric) -> String {
+    let metric_name = metric.metric_name.clone().unwrap_or_default();
+    if metric.dimensions.as_ref().map(|d| d.is_empty()).unwrap_or(true) {
+        metric_name
+    } else {
+        format!("{} {}", metric_name, format_dimensions(metric))
+    }
+}
+
+/// min and max of the values in all the series. [0, 1] if there's no datapoint
+pub fn get_value_bounds(series: &[MetricSeries]) -> [f64; 2] {
+    let values: Vec<f64> = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(_, v)| *v))
+        .collect();
+    if values.is_empty() {
+        return [0.0, 1.0];
+    }
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min).min(0.0);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max > min {
+        [min, max]
+    } else {
+        [min, min + 1.0]
+    }
+}
+
+/// log group which the metric is likely to be emitted with, guessed from its dimensions
+pub fn guess_log_group_name(metric: &Metric) -> Option<String> {
+    let dimensions = metric.dimensions.clone().unwrap_or_default();
+    let get = |name: &str| dimensions.iter().find(|d| d.name == name).map(|d| d.value.clone());
+    match metric.namespace.as_deref()? {
+        "AWS/Lambda" => get("FunctionName").map(|f| format!("/aws/lambda/{}", f)),
+        "AWS/CodeBuild" => get("ProjectName").map(|p| format!("/aws/codebuild/{}", p)),
+        _ => None,
+    }
+}
+
+/// time window on the chart (epoch millis) which is opened in the logs tab
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl TimeWindow {
+    /// window of a period at the highest datapoint, or at the end of the bounds if there's no datapoint
+    pub fn at_peak(series: &[MetricSeries], period_millis: i64, bounds: (i64, i64)) -> Self {
+        let peak = series
+            .iter()
+            .flat_map(|s| s.points.iter())
+            .fold(None, |peak: Option<(f64, f64)>, p| match peak {
+                Some(peak) if peak.1 >= p.1 => Some(peak),
+                _ => Some(*p),
+            });
+        let start = match peak {
+            Some((millis, _)) => millis as i64,
+            None => bounds.1 - period_millis,
+        };
+        Self { start, end: start + period_millis }.clamp(bounds)
+    }
+
+    /// move the window keeping its length
+    pub fn shift(self, delta: i64, bounds: (i64, i64)) -> Self {
+        let len = self.end - self.start;
+        let start = (self.start + delta).max(bounds.0).min(bounds.1 - len);
+        Self { start, end: start + len }
+    }
+
+    /// widen (positive `step`) or narrow (negative `step`) the window on both sides.
+    /// the window is never narrower than `min_len`
+    pub fn resize(self, step: i64, min_len: i64, bounds: (i64, i64)) -> Self {
+        if self.end - self.start + 2 * step < min_len {
+            return self;
+        }
+        Self { start: self.start - step, end: self.end + step }.clamp(bounds)
+    }
+
+    fn clamp(self, bounds: (i64, i64)) -> Self {
+        Self {
+            start: self.start.max(bounds.0),
+            end: self.end.min(bounds.1),
+        }
+    }
+}
+
+/// label of the time axis in the local timezone. the date is added for ranges over 1 hour
+pub fn format_time_label(millis: i64, range_millis: i64) -> String {
+    let format = if range_millis > 3_600_000 {
+        "%m-%d %H:%M"
+    } else {
+        "%H:%M:%S"
+    };
+    Local.timestamp_millis(millis).format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudwatch::Dimension;
+
+    #[test]
+    fn can_make_series_label() {
+        let mut metric = Metric {
+            metric_name: Some(String::from("Errors")),
+            ..Default::default()
+        };
+        assert_eq!("Errors", get_series_label(&metric));
+        metric.dimensions = Some(vec![Dimension { name: String::from("FunctionName"), value: String::from("f1") }]);
+        assert_eq!("Errors FunctionName=f1", get_series_label(&metric));
+    }
+
+    #[test]
+    fn can_get_value_bounds() {
+        assert_eq!([0.0, 1.0], get_value_bounds(&[]));
+        let series = vec![
+            MetricSeries { label: String::from("a"), points: vec![(0.0, 3.0), (1.0, 5.0)] },
+            MetricSeries { label: String::from("b"), points: vec![(0.0, 10.0)] },
+        ];
+        assert_eq!([0.0, 10.0], get_value_bounds(&series));
+        let series = vec![MetricSeries { label: String::from("a"), points: vec![(0.0, -2.0), (1.0, -2.0)] }];
+        assert_eq!([-2.0, -1.0], get_value_bounds(&series));
+    }
+
+    #[test]
+    fn can_guess_log_group_name() {
+        let metric = Metric {
+            namespace: Some(String::from("AWS/Lambda")),
+            metric_name: Some(String::from("Errors")),
+            dimensions: Some(vec![Dimension { name: String::from("FunctionName"), value: String::from("f1") }]),
+        };
+        assert_eq!(Some(String::from("/aws/lambda/f1")), guess_log_group_name(&metric));
+        let metric = Metric { namespace: Some(String::from("AWS/EC2")), ..metric };
+        assert_eq!(None, guess_log_group_name(&metric));
+    }
+
+    #[test]
+    fn window_starts_at_peak_and_moves_within_bounds() {
+        let series = vec![MetricSeries { label: String::from("a"), points: vec![(60_000.0, 1.0), (120_000.0, 9.0), (180_000.0, 2.0)] }];
+        let bounds = (0, 300_000);
+        let window = TimeWindow::at_peak(&series, 60_000, bounds);
+        assert_eq!(TimeWindow { start: 120_000, end: 180_000 }, window);
+        assert_eq!(TimeWindow { start: 240_000, end: 300_000 }, window.shift(600_000, bounds));
+        let window = window.resize(60_000, 60_000, bounds);
+        assert_eq!(TimeWindow { start: 60_000, end: 240_000 }, window);
+        let window = window.resize(-60_000, 60_000, bounds).resize(-60_000, 60_000, bounds);
+        assert_eq!(TimeWindow { start: 120_000, end: 180_000 }, window);
+        assert_eq!(TimeWindow { start: 240_000, end: 300_000 }, TimeWindow::at_peak(&[], 60_000, bounds));
+    }
+
+    #[test]
+    fn time_label_has_date_for_long_range() {
+        let millis = Local.ymd(2020, 12, 12).and_hms(1, 2, 3).timestamp_millis();
+        assert_eq!("01:02:03", format_time_label(millis, 3_600_000));
+        assert_eq!("12-12 01:02", format_time_label(millis, 43_200_000));
+    }
+}
@@ -1,7 +1,20 @@
-use tui::widgets::{ListState, TableState};
+use anyhow::{anyhow, Result};
+use chrono::{Local, TimeZone};
+use std::time::Duration;
+use tui::{
+    layout::{Layout, Constraint, Direction, Rect},
+    widgets::{ListState, TableState},
+};
 
+pub mod alarm_list;
 pub mod loggroup_menulist;
 pub mod logevent_list;
+pub mod logstream_menulist;
+pub mod metric_menulist;
+pub mod metric_series;
+pub mod queryresult_table;
+pub mod time_expression;
+pub mod timestamp_format;
 
 /// insert new lines at specified positions
 pub fn insert_new_line_at(at: usize, string: &str) -> String {
@@ -17,6 +30,95 @@ pub fn insert_new_line_at(at: usize, string: &str) -> String {
     result
 }
 
+/// parse a duration like "500ms", "30s", "5m", "2h" or "1d"
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    if let Some(value) = s.strip_suffix("ms") {
+        let millis: u64 = value.parse().map_err(|_| anyhow!("invalid duration: {}", s))?;
+        return Ok(Duration::from_millis(millis));
+    }
+    if s.len() < 2 {
+        return Err(anyhow!("invalid duration: {}", s));
+    }
+    let (value, unit) = s.split_at(s.len() - 1);
+    let value: u64 = value.parse().map_err(|_| anyhow!("invalid duration: {}", s))?;
+    let unit_secs: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(anyhow!("invalid duration unit: {}", s)),
+    };
+    let secs = value.checked_mul(unit_secs).ok_or_else(|| anyhow!("invalid duration: {}", s))?;
+    Ok(Duration::from_secs(secs))
+}
+
+/// format a duration in the largest unit which represents it exactly (e.g. "500ms", "90s", "2m")
+pub fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() != 0 {
+        return format!("{}ms", duration.as_millis());
+    }
+    let secs = duration.as_secs();
+    if secs == 0 {
+        return String::from("0s");
+    }
+    for (unit_secs, unit) in [(86400, "d"), (3600, "h"), (60, "m")].iter() {
+        let (count, rest) = (secs / unit_secs, secs % unit_secs);
+        if rest == 0 {
+            return format!("{}{}", count, unit);
+        }
+    }
+    format!("{}s", secs)
+}
+
+/// formats of local times accepted by `parse_time`
+pub(crate) const LOCAL_TIME_FORMATS: [&str; 3] = ["%Y-%m-%d-%H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"];
+
+/// parse a time expression (e.g. "2020-12-12-01:01:00", "-2h", "yesterday 09:00") in the local timezone
+/// into epoch millis. see `time_expression::parse_time_expression`
+pub fn parse_time(s: &str) -> Result<i64> {
+    time_expression::parse_time_expression(s, &Local::now())
+}
+
+/// parse the start and the end of a range. the range must not be empty nor end in the future
+pub fn parse_range(start: &str, end: &str, now: i64) -> Result<(i64, i64)> {
+    let local_now = Local.timestamp_millis(now);
+    let start = time_expression::parse_time_expression(start, &local_now)?;
+    let end = time_expression::parse_time_expression(end, &local_now)?;
+    if start >= end {
+        return Err(anyhow!("start must be before end"));
+    }
+    if end > now {
+        return Err(anyhow!("end must not be in the future"));
+    }
+    Ok((start, end))
+}
+
+/// format epoch millis as local time like "2020-12-12-01:01:00"
+pub fn format_local_time(millis: i64) -> String {
+    Local.timestamp_millis(millis).format(LOCAL_TIME_FORMATS[0]).to_string()
+}
+
+/// calculate the area of a popup placed in the center of `r`
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ].as_ref())
+        .split(r);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ].as_ref())
+        .split(popup_layout[1])[1]
+}
+
 pub trait StatefulList {
     fn get_labels(&self) -> Vec<String>;
     fn get_state(&mut self) -> Option<ListState>;
@@ -151,4 +253,61 @@ mod tests {
         let result = insert_new_line_at(4, &lines);
         assert_eq!(expect, result);
     }
+
+    #[test]
+    fn can_parse_time() {
+        assert_eq!(1607734860000, parse_time("1607734860000").unwrap());
+        assert_eq!(1607734860000, parse_time("2020-12-12T01:01:00Z").unwrap());
+        assert_eq!(1607734860000, parse_time("2020-12-12T10:01:00+09:00").unwrap());
+        assert!(parse_time("someday").is_err());
+        let local = Local.ymd(2020, 12, 12).and_hms(1, 1, 0).timestamp_millis();
+        assert_eq!(local, parse_time("2020-12-12-01:01:00").unwrap());
+        assert_eq!(local, parse_time("2020-12-12T01:01:00").unwrap());
+        assert_eq!("2020-12-12-01:01:00", format_local_time(local));
+    }
+
+    #[test]
+    fn can_parse_valid_range() {
+        let now = 1607734860000;
+        assert_eq!(
+            (1607731260000, now),
+            parse_range("2020-12-12T00:01:00Z", "2020-12-12T01:01:00Z", now).unwrap(),
+        );
+        assert!(parse_range("2020-12-12T01:01:00Z", "2020-12-12T00:01:00Z", now).is_err());
+        assert!(parse_range("2020-12-12T01:01:00Z", "2020-12-12T01:01:00Z", now).is_err());
+        assert!(parse_range("2020-12-12T00:01:00Z", "2020-12-12T01:01:01Z", now).is_err());
+        assert!(parse_range("2020-12-12", "2020-12-12T01:01:00Z", now).is_err());
+        assert_eq!((now - 7_200_000, now), parse_range("-2h", "now", now).unwrap());
+    }
+
+    #[test]
+    fn can_parse_duration() {
+        assert_eq!(Duration::from_secs(30), parse_duration("30s").unwrap());
+        assert_eq!(Duration::from_secs(300), parse_duration("5m").unwrap());
+        assert_eq!(Duration::from_secs(7200), parse_duration("2h").unwrap());
+        assert_eq!(Duration::from_secs(86400), parse_duration("1d").unwrap());
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("5y").is_err());
+        assert!(parse_duration("m").is_err());
+        assert_eq!(Duration::from_millis(500), parse_duration("500ms").unwrap());
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("99999999999999999d").is_err());
+    }
+
+    #[test]
+    fn can_format_duration() {
+        assert_eq!("500ms", format_duration(Duration::from_millis(500)));
+        assert_eq!("0s", format_duration(Duration::from_secs(0)));
+        assert_eq!("90s", format_duration(Duration::from_secs(90)));
+        assert_eq!("2m", format_duration(Duration::from_secs(120)));
+        assert_eq!("1h", format_duration(Duration::from_secs(3600)));
+        assert_eq!("1d", format_duration(Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn can_calculate_centered_rect() {
+        let area = Rect::new(0, 0, 100, 50);
+        let result = centered_rect(50, 50, area);
+        assert_eq!(Rect::new(25, 12, 50, 25), result);
+    }
 }
@@ -0,0 +1,142 @@
+use super::StatefulTable;
+use tui::widgets::TableState;
+use rusoto_logs::ResultField;
+
+/// field which is returned by Insights but isn't worth showing
+const PTR_FIELD: &str = "@ptr";
+
+/// results of an Insights query. columns are the returned fields
+#[derive(Debug)]
+pub struct QueryResultTable {
+    fields: Vec<String>,
+    rows: Vec<Vec<String>>,
+    state: Option<TableState>,
+}
+
+impl QueryResultTable {
+    pub fn new() -> Self {
+        Self {
+            fields: vec![],
+            rows: vec![],
+            state: Some(TableState::default()),
+        }
+    }
+
+    pub fn set_results(&mut self, results: &[Vec<ResultField>]) {
+        let mut fields: Vec<String> = vec![];
+        for result in results {
+            for field in result.iter().filter_map(|r| r.field.as_ref()) {
+                if field != PTR_FIELD && !fields.contains(field) {
+                    fields.push(field.clone());
+                }
+            }
+        }
+        self.rows = results
+            .iter()
+            .map(|result| {
+                fields
+                    .iter()
+                    .map(|field| {
+                        result
+                            .iter()
+                            .find(|r| r.field.as_ref() == Some(field))
+                            .and_then(|r| r.value.clone())
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+        self.fields = fields;
+    }
+
+    pub fn get_fields(&self) -> &Vec<String> {
+        &self.fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// "field: value" lines of the row
+    pub fn get_row_text(&self, idx: usize) -> Option<String> {
+        self.rows.get(idx).map(|row| {
+            self.fields
+                .iter()
+                .zip(row.iter())
+                .map(|(field, value)| format!("{}: {}", field, value))
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+    }
+
+    pub fn clear_items(&mut self) {
+        self.fields = vec![];
+        self.rows = vec![];
+        self.state = Some(TableState::default());
+    }
+
+    pub fn clone_with_state(&self, state: Option<TableState>) -> Self {
+        Self {
+            fields: self.fields.clone(),
+            rows: self.rows.clone(),
+            state,
+        }
+    }
+
+    pub fn is_same(&self, other: &Self) -> bool {
+        self.fields == other.fields && self.rows == other.rows
+    }
+}
+
+impl Default for QueryResultTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatefulTable for QueryResultTable {
+    fn get_labels(&self) -> Vec<Vec<String>> {
+        self.rows.clone()
+    }
+    fn get_state(&mut self) -> Option<TableState> {
+        self.state.clone()
+    }
+    fn set_state(&mut self, new_state: TableState) {
+        self.state = Some(new_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(field: &str, value: &str) -> ResultField {
+        ResultField {
+            field: Some(field.to_string()),
+            value: Some(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn can_make_columns_from_fields() {
+        let mut table = QueryResultTable::new();
+        table.set_results(&[
+            vec![field("bin(5m)", "2020-01-01 00:00:00.000"), field("count(*)", "3"), field("@ptr", "xxx")],
+            vec![field("count(*)", "5"), field("level", "ERROR")],
+        ]);
+        assert_eq!(&vec![String::from("bin(5m)"), String::from("count(*)"), String::from("level")], table.get_fields());
+        assert_eq!(
+            vec![
+                vec![String::from("2020-01-01 00:00:00.000"), String::from("3"), String::from("")],
+                vec![String::from(""), String::from("5"), String::from("ERROR")],
+            ],
+            table.get_labels(),
+        );
+        assert_eq!(Some(String::from("bin(5m): \ncount(*): 5\nlevel: ERROR")), table.get_row_text(1));
+        assert_eq!(None, table.get_row_text(2));
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Weekday};
+use std::convert::TryFrom;
+use super::{LOCAL_TIME_FORMATS, parse_duration};
+
+/// parse a time expression into epoch millis. `now` decides the current time and the timezone
+/// of the expressions without an offset. accepted expressions are
+/// - absolute: epoch millis, RFC 3339, "2020-12-12-01:01:00", "2020-12-12T01:01:00"
+/// - relative to now: "now", "-90m", "now-2d", "now-1d+2h", "5m" (same as "-5m")
+/// - anchors with an optional time: "today", "yesterday 09:00", "last monday 12:30:00"
+pub fn parse_time_expression<Tz: TimeZone>(s: &str, now: &DateTime<Tz>) -> Result<i64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(anyhow!("empty time"));
+    }
+    if s.chars().all(|c| c.is_ascii_digit()) {
+        return s.parse::<i64>().map_err(|_| anyhow!("invalid time: {}", s));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.timestamp_millis());
+    }
+    if let Some(time) = LOCAL_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    {
+        return now.timezone()
+            .from_local_datetime(&time)
+            .earliest()
+            .map(|time| time.timestamp_millis())
+            .ok_or_else(|| anyhow!("nonexistent local time: {}", s));
+    }
+    let s = s.to_lowercase();
+    parse_relative(&s, now)
+        .or_else(|| parse_anchor(&s, now))
+        .unwrap_or_else(|| Err(anyhow!("invalid time: {}", s)))
+}
+
+/// None if the expression isn't relative to now
+fn parse_relative<Tz: TimeZone>(s: &str, now: &DateTime<Tz>) -> Option<Result<i64>> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let offsets = if let Some(offsets) = s.strip_prefix("now") {
+        offsets.to_string()
+    } else if s.starts_with('-') || s.starts_with('+') {
+        s
+    } else if parse_duration(&s).is_ok() {
+        format!("-{}", s)
+    } else {
+        return None;
+    };
+    Some(parse_offsets(&offsets).and_then(|offset| {
+        now.timestamp_millis().checked_add(offset).ok_or_else(|| anyhow!("invalid offset: {}", offsets))
+    }))
+}
+
+/// sum of the offsets like "-1d+2h" in millis
+fn parse_offsets(s: &str) -> Result<i64> {
+    let mut total: i64 = 0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let sign = match rest.chars().next() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => return Err(anyhow!("invalid offset: {}", s)),
+        };
+        rest = &rest[1..];
+        let end = rest.find(&['+', '-'][..]).unwrap_or(rest.len());
+        let millis = i64::try_from(parse_duration(&rest[..end])?.as_millis()).map_err(|_| anyhow!("invalid offset: {}", s))?;
+        total = total.checked_add(sign * millis).ok_or_else(|| anyhow!("invalid offset: {}", s))?;
+        rest = &rest[end..];
+    }
+    Ok(total)
+}
+
+/// None if the expression doesn't start with an anchor
+fn parse_anchor<Tz: TimeZone>(s: &str, now: &DateTime<Tz>) -> Option<Result<i64>> {
+    let today = now.date();
+    let mut words = s.split_whitespace();
+    let date = match words.next()? {
+        "today" => today,
+        "yesterday" => today.pred(),
+        "last" => {
+            let weekday = match words.next().map(|w| w.parse::<Weekday>()) {
+                Some(Ok(weekday)) => weekday,
+                _ => return Some(Err(anyhow!("invalid weekday: {}", s))),
+            };
+            let mut date = today.pred();
+            while date.weekday() != weekday {
+                date = date.pred();
+            }
+            date
+        },
+        _ => return None,
+    };
+    let (hour, minute, second) = match words.next() {
+        Some(time) => match parse_clock(time) {
+            Some(clock) => clock,
+            None => return Some(Err(anyhow!("invalid time of day: {}", time))),
+        },
+        None => (0, 0, 0),
+    };
+    if words.next().is_some() {
+        return Some(Err(anyhow!("invalid time: {}", s)));
+    }
+    Some(
+        date.and_hms_opt(hour, minute, second)
+            .map(|time| time.timestamp_millis())
+            .ok_or_else(|| anyhow!("invalid time: {}", s))
+    )
+}
+
+/// "09:00" or "09:00:30"
+fn parse_clock(s: &str) -> Option<(u32, u32, u32)> {
+    let parts: Vec<u32> = s.split(':').map(|p| p.parse().ok()).collect::<Option<Vec<u32>>>()?;
+    let (hour, minute, second) = match parts.as_slice() {
+        [hour, minute] => (*hour, *minute, 0),
+        [hour, minute, second] => (*hour, *minute, *second),
+        _ => return None,
+    };
+    if hour < 24 && minute < 60 && second < 60 {
+        Some((hour, minute, second))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    const MINUTE: i64 = 60_000;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+
+    /// Wednesday 2020-12-16 01:30:00 in +09:00, which is still Tuesday in UTC
+    fn tokyo_now() -> DateTime<FixedOffset> {
+        FixedOffset::east(9 * 3600).ymd(2020, 12, 16).and_hms(1, 30, 0)
+    }
+
+    fn millis(rfc3339: &str) -> i64 {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().timestamp_millis()
+    }
+
+    #[test]
+    fn can_parse_absolute_times() {
+        let now = tokyo_now();
+        assert_eq!(1607734860000, parse_time_expression("1607734860000", &now).unwrap());
+        assert_eq!(1607734860000, parse_time_expression("2020-12-12T01:01:00Z", &now).unwrap());
+        // times without an offset are in the timezone of now
+        assert_eq!(millis("2020-12-12T01:01:00+09:00"), parse_time_expression("2020-12-12-01:01:00", &now).unwrap());
+        assert_eq!(millis("2020-12-12T01:01:00+09:00"), parse_time_expression("2020-12-12T01:01:00", &now).unwrap());
+        let now = now.with_timezone(&FixedOffset::west(5 * 3600));
+        assert_eq!(millis("2020-12-12T01:01:00-05:00"), parse_time_expression("2020-12-12 01:01:00", &now).unwrap());
+    }
+
+    #[test]
+    fn can_parse_offsets_from_now() {
+        let now = tokyo_now();
+        let base = now.timestamp_millis();
+        assert_eq!(base, parse_time_expression("now", &now).unwrap());
+        assert_eq!(base - 90 * MINUTE, parse_time_expression("-90m", &now).unwrap());
+        assert_eq!(base - 2 * DAY, parse_time_expression("now-2d", &now).unwrap());
+        assert_eq!(base - 2 * DAY, parse_time_expression(" NOW - 2d ", &now).unwrap());
+        assert_eq!(base - DAY + 2 * HOUR, parse_time_expression("now-1d+2h", &now).unwrap());
+        assert_eq!(base + 30_000, parse_time_expression("+30s", &now).unwrap());
+        assert_eq!(base - 5 * MINUTE, parse_time_expression("5m", &now).unwrap());
+    }
+
+    #[test]
+    fn anchors_are_in_the_timezone_of_now() {
+        let now = tokyo_now();
+        assert_eq!(millis("2020-12-16T00:00:00+09:00"), parse_time_expression("today", &now).unwrap());
+        assert_eq!(millis("2020-12-15T09:00:00+09:00"), parse_time_expression("yesterday 09:00", &now).unwrap());
+        assert_eq!(millis("2020-12-15T23:59:30+09:00"), parse_time_expression("Yesterday 23:59:30", &now).unwrap());
+        // the same instant is Tuesday 2020-12-15 11:30 in -05:00
+        let now = now.with_timezone(&FixedOffset::west(5 * 3600));
+        assert_eq!(millis("2020-12-15T00:00:00-05:00"), parse_time_expression("today", &now).unwrap());
+        let now = now.with_timezone(&Utc);
+        assert_eq!(millis("2020-12-14T00:00:00Z"), parse_time_expression("yesterday", &now).unwrap());
+    }
+
+    #[test]
+    fn last_weekday_is_before_today() {
+        let now = tokyo_now();
+        assert_eq!(millis("2020-12-14T00:00:00+09:00"), parse_time_expression("last monday", &now).unwrap());
+        assert_eq!(millis("2020-12-15T12:00:00+09:00"), parse_time_expression("last tue 12:00", &now).unwrap());
+        assert_eq!(millis("2020-12-09T00:00:00+09:00"), parse_time_expression("last wednesday", &now).unwrap());
+        assert_eq!(millis("2020-12-10T00:00:00+09:00"), parse_time_expression("last thursday", &now).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        let now = tokyo_now();
+        for s in &["", "now-", "now*2d", "-2x", "-2", "tomorrow", "last", "last someday", "yesterday 25:00", "today 09", "today 09:00 x", "2020-12-12", "-99999999999999999d", "-9999999999999999s-9999999999999999s"] {
+            assert!(parse_time_expression(s, &now).is_err(), "{} should be invalid", s);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, TimeZone, Utc};
+use chrono::format::{Item, StrftimeItems};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// timezone which timestamps are shown in
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimestampZone {
+    Utc,
+    Local,
+    /// IANA zone like "Asia/Tokyo"
+    Named(Tz),
+}
+
+impl FromStr for TimestampZone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "utc" => Ok(TimestampZone::Utc),
+            "local" => Ok(TimestampZone::Local),
+            _ => s.parse::<Tz>()
+                .map(TimestampZone::Named)
+                .map_err(|_| anyhow!("unknown timezone: {}", s)),
+        }
+    }
+}
+
+/// how timestamps of log events are shown in the tables, the full text and the plain output
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampFormat {
+    pub zone: TimestampZone,
+    /// strftime-like format of chrono
+    pub format: String,
+    /// add milliseconds after the seconds
+    pub millis: bool,
+    /// show the elapsed time like "3m ago" instead
+    pub relative: bool,
+}
+
+impl Default for TimestampFormat {
+    fn default() -> Self {
+        Self {
+            zone: TimestampZone::Utc,
+            format: String::from("%Y-%m-%d %H:%M:%S %Z"),
+            millis: false,
+            relative: false,
+        }
+    }
+}
+
+impl TimestampFormat {
+    /// reject the format which chrono can't format. formatting it panics
+    pub fn validate(&self) -> Result<()> {
+        if StrftimeItems::new(&self.format).any(|item| item == Item::Error) {
+            return Err(anyhow!("invalid time format: {}", self.format));
+        }
+        Ok(())
+    }
+
+    /// format epoch millis. `now` is used for the relative format
+    pub fn format(&self, millis: i64, now: i64) -> String {
+        if self.relative {
+            return format_elapsed(now - millis);
+        }
+        let format = if self.millis && !self.format.contains("%.3f") {
+            self.format.replacen("%S", "%S%.3f", 1)
+        } else {
+            self.format.clone()
+        };
+        match self.zone {
+            TimestampZone::Utc => Utc.timestamp_millis(millis).format(&format).to_string(),
+            TimestampZone::Local => Local.timestamp_millis(millis).format(&format).to_string(),
+            TimestampZone::Named(tz) => tz.timestamp_millis(millis).format(&format).to_string(),
+        }
+    }
+}
+
+/// "12s ago", "3m ago", "2h ago" or "5d ago"
+fn format_elapsed(elapsed_millis: i64) -> String {
+    let secs = elapsed_millis.max(0) / 1000;
+    match secs {
+        s if s < 60 => format!("{}s ago", s),
+        s if s < 3600 => format!("{}m ago", s / 60),
+        s if s < 86400 => format!("{}h ago", s / 3600),
+        s => format!("{}d ago", s / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MILLIS: i64 = 1607734860123;
+
+    #[test]
+    fn can_parse_zone() {
+        assert_eq!(TimestampZone::Utc, "UTC".parse().unwrap());
+        assert_eq!(TimestampZone::Local, "local".parse().unwrap());
+        assert_eq!(TimestampZone::Named(chrono_tz::Asia::Tokyo), "Asia/Tokyo".parse().unwrap());
+        assert!("tokyo".parse::<TimestampZone>().is_err());
+        assert!("../etc/passwd".parse::<TimestampZone>().is_err());
+    }
+
+    #[test]
+    fn can_format_with_millis_and_custom_format() {
+        let mut format = TimestampFormat::default();
+        assert_eq!("2020-12-12 01:01:00 UTC", format.format(MILLIS, 0));
+        format.millis = true;
+        assert_eq!("2020-12-12 01:01:00.123 UTC", format.format(MILLIS, 0));
+        format.format = String::from("%H:%M:%S");
+        assert_eq!("01:01:00.123", format.format(MILLIS, 0));
+        format.zone = TimestampZone::Local;
+        let expected = Local.timestamp_millis(MILLIS).format("%H:%M:%S%.3f").to_string();
+        assert_eq!(expected, format.format(MILLIS, 0));
+        format.zone = "Asia/Tokyo".parse().unwrap();
+        assert_eq!("10:01:00.123", format.format(MILLIS, 0));
+    }
+
+    #[test]
+    fn unknown_specifier_is_invalid() {
+        let mut format = TimestampFormat::default();
+        assert!(format.validate().is_ok());
+        format.format = String::from("%Q");
+        assert!(format.validate().is_err());
+        format.format = String::from("%Y-%m-%d %");
+        assert!(format.validate().is_err());
+    }
+
+    #[test]
+    fn can_format_relative() {
+        let format = TimestampFormat {
+            relative: true,
+            ..Default::default()
+        };
+        assert_eq!("12s ago", format.format(MILLIS, MILLIS + 12_500));
+        assert_eq!("3m ago", format.format(MILLIS, MILLIS + 200_000));
+        assert_eq!("2h ago", format.format(MILLIS, MILLIS + 7_300_000));
+        assert_eq!("5d ago", format.format(MILLIS, MILLIS + 5 * 86_400_000));
+        assert_eq!("0s ago", format.format(MILLIS, MILLIS - 1000));
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::StubServer;
+use megane::aws::{AwsSettings, ClientHolder, credentials::MfaPrompt};
+use megane::globalstate::{GlobalState, GlobalStateMetrics};
+use megane::handler::InstructionHandler;
+use megane::instruction::Instruction;
+use megane::logsource::rusoto::RusotoLogSource;
+use megane::metricsource::rusoto::RusotoMetricSource;
+use megane::utils::StatefulList;
+use rusoto_core::Region;
+use rusoto_logs::{CloudWatchLogs, DescribeLogGroupsRequest};
+use std::sync::{Arc, Mutex};
+
+const DESCRIBE_LOG_GROUPS_RESPONSE: &str = r#"{"logGroups":[{"logGroupName":"/aws/lambda/stub","arn":"arn:stub","storedBytes":10}]}"#;
+
+fn set_dummy_credentials() {
+    std::env::set_var("AWS_ACCESS_KEY_ID", "AKIADUMMY");
+    std::env::set_var("AWS_SECRET_ACCESS_KEY", "dummy-secret");
+}
+
+#[tokio::test]
+async fn logs_client_uses_custom_endpoint() {
+    set_dummy_credentials();
+    let server = StubServer::start(vec![DESCRIBE_LOG_GROUPS_RESPONSE.to_string()]).await;
+    let settings = AwsSettings::new(Region::ApNortheast1, None, Some(server.endpoint.clone()));
+    let client = settings.logs_client(&MfaPrompt::new()).unwrap();
+    let response = client.describe_log_groups(DescribeLogGroupsRequest {
+        log_group_name_prefix: Some(String::from("/aws/lambda")),
+        ..Default::default()
+    }).await.unwrap();
+    let names: Vec<Option<String>> = response.log_groups.unwrap()
+        .into_iter()
+        .map(|g| g.log_group_name)
+        .collect();
+    assert_eq!(vec![Some(String::from("/aws/lambda/stub"))], names);
+    let requests = server.get_requests();
+    assert_eq!(1, requests.len());
+    assert!(requests[0].contains("\"logGroupNamePrefix\":\"/aws/lambda\""));
+}
+
+#[tokio::test]
+async fn client_holder_follows_endpoint_change() {
+    set_dummy_credentials();
+    let server1 = StubServer::start(vec![DESCRIBE_LOG_GROUPS_RESPONSE.to_string()]).await;
+    let server2 = StubServer::start(vec![DESCRIBE_LOG_GROUPS_RESPONSE.to_string()]).await;
+    let settings = Arc::new(Mutex::new(
+        AwsSettings::new(Region::UsEast1, None, Some(server1.endpoint.clone()))
+    ));
+    let holder = ClientHolder::new(Arc::clone(&settings), MfaPrompt::new()).unwrap();
+    holder.logs_client().unwrap().describe_log_groups(DescribeLogGroupsRequest::default()).await.unwrap();
+    settings.lock().unwrap().endpoint_url = Some(server2.endpoint.clone());
+    holder.logs_client().unwrap().describe_log_groups(DescribeLogGroupsRequest::default()).await.unwrap();
+    assert_eq!(1, server1.get_requests().len());
+    assert_eq!(1, server2.get_requests().len());
+}
+
+#[tokio::test]
+async fn can_fetch_log_groups_through_stub_endpoint() {
+    set_dummy_credentials();
+    let server = StubServer::start(vec![
+        r#"{"logGroups":[{"logGroupName":"group1"}],"nextToken":"token1"}"#.to_string(),
+        r#"{"logGroups":[{"logGroupName":"group2"}]}"#.to_string(),
+    ]).await;
+    let settings = Arc::new(Mutex::new(
+        AwsSettings::new(Region::UsEast1, None, Some(server.endpoint.clone()))
+    ));
+    let holder = ClientHolder::new(settings, MfaPrompt::new()).unwrap();
+    let state = Arc::new(Mutex::new(GlobalState::new()));
+    let metrics_state = Arc::new(Mutex::new(GlobalStateMetrics::new()));
+    let handler = InstructionHandler::new(
+        RusotoLogSource::new(holder.clone()),
+        RusotoMetricSource::new(holder),
+        Arc::clone(&state),
+        metrics_state,
+    );
+    handler.handle(Instruction::FetchLogGroups).await;
+    assert_eq!(
+        vec![String::from("group1"), String::from("group2")],
+        state.lock().unwrap().log_groups.get_labels(),
+    );
+    let requests = server.get_requests();
+    assert_eq!(2, requests.len());
+    assert!(requests[1].contains("\"nextToken\":\"token1\""));
+}
@@ -0,0 +1,74 @@
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::prelude::*;
+
+/// minimal HTTP server which replies the given responses in order (the last one is repeated)
+/// and records the request bodies
+pub struct StubServer {
+    pub endpoint: String,
+    pub requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubServer {
+    pub async fn start(responses: Vec<String>) -> Self {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let requests0 = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let body = read_request_body(&mut socket).await;
+                let idx = {
+                    let mut requests = requests0.lock().unwrap();
+                    requests.push(body);
+                    requests.len() - 1
+                };
+                let response = responses
+                    .get(idx)
+                    .or_else(|| responses.last())
+                    .cloned()
+                    .unwrap_or_default();
+                let http = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response,
+                );
+                let _ = socket.write_all(http.as_bytes()).await;
+                let _ = socket.shutdown(std::net::Shutdown::Write);
+            }
+        });
+        Self {
+            endpoint,
+            requests,
+        }
+    }
+
+    pub fn get_requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request_body(socket: &mut tokio::net::TcpStream) -> String {
+    let mut buf = vec![];
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut chunk).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&buf).to_string();
+        if let Some(pos) = text.find("\r\n\r\n") {
+            let content_length = text[..pos]
+                .lines()
+                .find(|line| line.to_lowercase().starts_with("content-length:"))
+                .and_then(|line| line[15..].trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if buf.len() >= pos + 4 + content_length {
+                return text[pos + 4..].to_string();
+            }
+        }
+    }
+    String::from_utf8_lossy(&buf).to_string()
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::StubServer;
+use megane::aws::credentials::{AssumeRoleProvider, MfaPrompt, RoleProfile};
+use rusoto_core::{HttpClient, Region};
+use rusoto_credential::{AutoRefreshingProvider, ProvideAwsCredentials, StaticProvider};
+use rusoto_sts::StsClient;
+
+const ASSUME_ROLE_RESPONSE: &str = r#"<AssumeRoleResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+  <AssumeRoleResult>
+    <Credentials>
+      <AccessKeyId>ASIATEMPORARY</AccessKeyId>
+      <SecretAccessKey>temporary-secret</SecretAccessKey>
+      <SessionToken>temporary-token</SessionToken>
+      <Expiration>2099-01-01T00:00:00Z</Expiration>
+    </Credentials>
+    <AssumedRoleUser>
+      <Arn>arn:aws:sts::123456789012:assumed-role/admin/megane</Arn>
+      <AssumedRoleId>AROAEXAMPLE:megane</AssumedRoleId>
+    </AssumedRoleUser>
+  </AssumeRoleResult>
+  <ResponseMetadata>
+    <RequestId>00000000-0000-0000-0000-000000000000</RequestId>
+  </ResponseMetadata>
+</AssumeRoleResponse>"#;
+
+fn make_role(mfa_serial: Option<&str>) -> RoleProfile {
+    RoleProfile {
+        role_arn: String::from("arn:aws:iam::123456789012:role/admin"),
+        source_profile: None,
+        mfa_serial: mfa_serial.map(|s| s.to_string()),
+        external_id: None,
+        duration_seconds: None,
+        role_session_name: None,
+    }
+}
+
+fn make_sts_client(endpoint: &str) -> StsClient {
+    StsClient::new_with(
+        HttpClient::new().unwrap(),
+        StaticProvider::new_minimal(String::from("AKIASOURCE"), String::from("source-secret")),
+        Region::Custom {
+            name: String::from("us-east-1"),
+            endpoint: endpoint.to_string(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn assume_role_provider_asks_mfa_token_code() {
+    let server = StubServer::start(vec![ASSUME_ROLE_RESPONSE.to_string()]).await;
+    let prompt = MfaPrompt::new();
+    let provider = AssumeRoleProvider::new(
+        make_sts_client(&server.endpoint),
+        make_role(Some("arn:aws:iam::000000000000:mfa/user")),
+        prompt.clone(),
+    );
+    let handle = tokio::spawn(async move {
+        provider.credentials().await
+    });
+    while prompt.get_pending_serial().is_none() {
+        tokio::time::delay_for(std::time::Duration::from_millis(1)).await;
+    }
+    assert_eq!(
+        Some(String::from("arn:aws:iam::000000000000:mfa/user")),
+        prompt.get_pending_serial(),
+    );
+    prompt.answer(Some(String::from("123456")));
+    let credentials = handle.await.unwrap().unwrap();
+    assert_eq!("ASIATEMPORARY", credentials.aws_access_key_id());
+    assert_eq!(Some(&String::from("temporary-token")), credentials.token().as_ref());
+    let requests = server.get_requests();
+    assert_eq!(1, requests.len());
+    assert!(requests[0].contains("Action=AssumeRole"));
+    assert!(requests[0].contains("TokenCode=123456"));
+}
+
+#[tokio::test]
+async fn assume_role_provider_fails_when_mfa_is_cancelled() {
+    let server = StubServer::start(vec![ASSUME_ROLE_RESPONSE.to_string()]).await;
+    let prompt = MfaPrompt::new();
+    let provider = AssumeRoleProvider::new(
+        make_sts_client(&server.endpoint),
+        make_role(Some("serial")),
+        prompt.clone(),
+    );
+    let handle = tokio::spawn(async move {
+        provider.credentials().await
+    });
+    while prompt.get_pending_serial().is_none() {
+        tokio::time::delay_for(std::time::Duration::from_millis(1)).await;
+    }
+    prompt.answer(None);
+    assert!(handle.await.unwrap().is_err());
+    assert!(server.get_requests().is_empty());
+}
+
+#[tokio::test]
+async fn assumed_credentials_are_cached_until_expiration() {
+    let server = StubServer::start(vec![ASSUME_ROLE_RESPONSE.to_string()]).await;
+    let provider = AutoRefreshingProvider::new(AssumeRoleProvider::new(
+        make_sts_client(&server.endpoint),
+        make_role(None),
+        MfaPrompt::new(),
+    )).unwrap();
+    let first = provider.credentials().await.unwrap();
+    let second = provider.credentials().await.unwrap();
+    assert_eq!(first.aws_access_key_id(), second.aws_access_key_id());
+    assert_eq!(1, server.get_requests().len());
+}